@@ -30,7 +30,14 @@ impl App {
     }
 
     pub fn add_addon_inferred(&mut self, addon: Downloadable) -> Result<()> {
-        let addon_type = match self.server.jar.get_software_type() {
+        let addon_type = self.infer_addon_type()?;
+
+        self.add_addon(addon_type, addon)
+    }
+
+    /// Whether addons are mods or plugins for this server, asks if it can't tell
+    pub fn infer_addon_type(&self) -> Result<AddonType> {
+        Ok(match self.server.jar.get_software_type() {
             SoftwareType::Modded => AddonType::Mod,
             SoftwareType::Normal | SoftwareType::Proxy => AddonType::Plugin,
             SoftwareType::Unknown => self.select(
@@ -40,9 +47,7 @@ impl App {
                     SelectItem(AddonType::Plugin, Cow::Borrowed("Plugin")),
                 ],
             )?,
-        };
-
-        self.add_addon(addon_type, addon)
+        })
     }
 
     pub fn add_addon(&mut self, addon_type: AddonType, addon: Downloadable) -> Result<()> {
@@ -1,19 +1,51 @@
-use std::{borrow::Cow, fmt::Debug, fs, path::PathBuf, time::Duration};
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use anyhow::{bail, Context, Result};
 use digest::{Digest, DynDigest};
 use indicatif::{ProgressBar, ProgressStyle};
 use md5::Md5;
+use reqwest::RequestBuilder;
+use serde::Serialize;
 use sha1::Sha1;
 use sha2::{Sha256, Sha512};
-use tokio::{fs::File, io::BufWriter};
+use tokio::{
+    fs::File,
+    io::{AsyncWriteExt, BufWriter},
+};
 use tokio_stream::StreamExt;
 use tokio_util::io::ReaderStream;
 
-use crate::util::SelectItem;
+use crate::util::{self, SelectItem};
 
 use super::{App, CacheStrategy, Prefix, ProgressPrefix, Resolvable, ResolvedFile};
 
+/// Where [`App::fetch_resolved`] got a file from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileOrigin {
+    /// Already in the output directory
+    Existing,
+    /// Copied from the cache
+    Cached,
+    /// Copied from a `file` addon
+    Local,
+    Downloaded,
+}
+
+/// The sha256 and size a file had in the lockfile, from when it was last downloaded
+#[derive(Debug, Clone)]
+pub struct KnownFile {
+    pub sha256: String,
+    /// Missing in older lockfiles
+    pub size: Option<u64>,
+}
+
 struct Bomb<T: FnMut()>(pub bool, pub T);
 
 impl<T: FnMut()> Bomb<T> {
@@ -36,6 +68,18 @@ impl App {
         resolvable: &(impl Resolvable + ToString + Debug),
         destination: PathBuf,
         progress_bar: ProgressBar,
+    ) -> Result<ResolvedFile> {
+        let resolved = self.resolve(resolvable, &progress_bar).await?;
+
+        self.download_resolved(resolved, destination, progress_bar)
+            .await
+    }
+
+    /// Resolve while showing it on the progress bar
+    pub async fn resolve(
+        &self,
+        resolvable: &(impl Resolvable + ToString + Debug),
+        progress_bar: &ProgressBar,
     ) -> Result<ResolvedFile> {
         progress_bar.set_style(ProgressStyle::with_template(
             "{spinner:.blue} {prefix} {msg}...",
@@ -44,13 +88,10 @@ impl App {
         progress_bar.set_message(resolvable.to_string());
         progress_bar.enable_steady_tick(Duration::from_millis(250));
 
-        let resolved = resolvable
+        resolvable
             .resolve_source(self)
             .await
-            .context(format!("Resolving {resolvable:#?}"))?;
-
-        self.download_resolved(resolved, destination, progress_bar)
-            .await
+            .context(format!("Resolving {resolvable:#?}"))
     }
 
     pub fn resolve_cached_file(&self, cache: &CacheStrategy) -> Option<(PathBuf, bool)> {
@@ -58,11 +99,35 @@ impl App {
             CacheStrategy::File { namespace, path } => self
                 .get_cache(namespace)
                 .map(|cache| (cache.path(path), cache.exists(path))),
+            CacheStrategy::Local { path } => {
+                let path = self.server.path.join(path);
+                let exists = path.is_file();
+                Some((path, exists))
+            }
+            CacheStrategy::GithubArtifact { path } => {
+                let path = self.github().artifact_dir(path);
+                let exists = path.is_file();
+                Some((path, exists))
+            }
             CacheStrategy::Indexed { .. } => todo!(),
             CacheStrategy::None => None,
         }
     }
 
+    /// Add the configured token or credentials for the instance `url` is on, so files
+    /// of private GitLab, Gitea and Maven repositories can be downloaded
+    pub fn with_source_auth(&self, request: RequestBuilder, url: &str) -> RequestBuilder {
+        if let Some(token) = self.gitlab().get_token(url) {
+            request.header("PRIVATE-TOKEN", token)
+        } else if let Some(token) = self.gitea().get_token(url) {
+            request.header("Authorization", format!("token {token}"))
+        } else if let Some((username, password)) = self.maven().get_credentials(url) {
+            request.basic_auth(username, password)
+        } else {
+            request
+        }
+    }
+
     pub fn create_hasher(name: &str) -> Box<dyn DynDigest> {
         match name {
             "sha256" => Box::new(<Sha256 as Digest>::new()),
@@ -73,52 +138,261 @@ impl App {
         }
     }
 
-    #[allow(clippy::too_many_lines)]
     pub async fn download_resolved(
         &self,
         resolved: ResolvedFile,
         destination: PathBuf,
         progress_bar: ProgressBar,
     ) -> Result<ResolvedFile> {
-        let progress_bar = progress_bar.with_finish(indicatif::ProgressFinish::AndClear);
-        progress_bar.set_style(ProgressStyle::with_template(
-            "{spinner:.blue} {prefix} {msg}...",
-        )?);
-        progress_bar.set_prefix(ProgressPrefix::Checking);
-        progress_bar.enable_steady_tick(Duration::from_millis(250));
+        self.fetch_resolved(resolved, destination, progress_bar, None)
+            .await
+            .map(|(resolved, _)| resolved)
+    }
 
-        // Some(Path) if file exists in cache
-        let cached_file_path = self.resolve_cached_file(&resolved.cache);
+    /// Add the sha256 and size of the downloaded file to `resolved` if it doesn't have
+    /// them, so the lockfile can always be verified with it. The sha256 in `known` is
+    /// reused instead of hashing the file again if the size didn't change
+    async fn with_sha256(
+        mut resolved: ResolvedFile,
+        file_path: &Path,
+        known: Option<&KnownFile>,
+    ) -> Result<ResolvedFile> {
+        let size = file_path
+            .metadata()
+            .context(format!(
+                "Getting metadata of file '{}'",
+                file_path.display()
+            ))?
+            .len();
+        resolved.size.get_or_insert(size);
+
+        if !resolved.hashes.contains_key("sha256") {
+            let hash = match known {
+                Some(known) if known.size == Some(size) => known.sha256.clone(),
+                // boxed so it doesn't grow the future of every download
+                _ => Box::pin(Self::hash_file(file_path, "sha256")).await?,
+            };
+            resolved.hashes.insert("sha256".to_owned(), hash);
+        }
+
+        Ok(resolved)
+    }
 
-        let hasher = Self::get_best_hash(&resolved.hashes);
+    /// `resolved` with a sha256 and size if the existing file at `path` matches its hashes,
+    /// or the sha256 in `known` if it has none. `None` if it doesn't match or can't be checked,
+    /// so it has to be downloaded again
+    async fn check_existing(
+        &self,
+        resolved: &ResolvedFile,
+        path: &Path,
+        known: Option<&KnownFile>,
+    ) -> Result<Option<ResolvedFile>> {
+        let mut resolved = resolved.clone();
+
+        if Self::resolved_hashers(&resolved).is_empty() {
+            let Some(known) = known else {
+                return Ok(None);
+            };
+            resolved
+                .hashes
+                .insert("sha256".to_owned(), known.sha256.clone());
+        }
 
-        // if resolved has hashes, Some((hash name, dyndigest, hash value))
-        let mut hasher = hasher.map(|(name, hash)| {
-            let digester: Box<dyn DynDigest> = App::create_hasher(&name);
+        let mut hashers = Self::resolved_hashers(&resolved);
+        let mut sha256 = Self::create_hasher("sha256");
 
-            (name, digester, hash)
-        });
+        let file = File::open(path)
+            .await
+            .context(format!("Opening '{}'", path.display()))?;
+        let mut stream = ReaderStream::new(file);
+        let mut size = 0;
+        while let Some(item) = stream.next().await {
+            let item = item?;
+
+            for (_, digest, _) in &mut hashers {
+                digest.update(&item);
+            }
+            sha256.update(&item);
+            size += item.len() as u64;
+        }
 
-        let validate_hash = |hasher: Option<(String, Box<dyn DynDigest>, String)>| {
-            if let Some((hash_name, digest, resolved_hash)) = hasher {
-                let stream_hash = hex::encode(&digest.finalize());
+        for (hash_name, digest, hash) in hashers {
+            if !hash.eq_ignore_ascii_case(&hex::encode(digest.finalize())) {
+                self.warn(format!(
+                    "{} doesn't match its {hash_name} hash, downloading it again",
+                    path.display()
+                ));
+                return Ok(None);
+            }
+        }
 
-                if resolved_hash == stream_hash {
-                    self.dbg("hash check success");
-                } else {
-                    // TODO: skipping checks etc
-                    // also pretty msg
-                    bail!(
-                        "Mismatched hash!
+        resolved
+            .hashes
+            .entry("sha256".to_owned())
+            .or_insert_with(|| hex::encode(sha256.finalize()));
+        resolved.size.get_or_insert(size);
+
+        Ok(Some(resolved))
+    }
+
+    /// Hashers for every hash of `resolved` that can be checked, as (hash name, dyndigest, hash value).
+    /// All of them are checked, so a pinned `hash` counts even if the source has a better one
+    fn resolved_hashers(resolved: &ResolvedFile) -> Vec<(String, Box<dyn DynDigest>, String)> {
+        ["sha512", "sha256", "md5", "sha1"]
+            .into_iter()
+            .filter_map(|name| {
+                let hash = resolved.hashes.get(name)?;
+                Some((name.to_owned(), App::create_hasher(name), hash.clone()))
+            })
+            .collect()
+    }
+
+    fn validate_hashes(&self, hashers: Vec<(String, Box<dyn DynDigest>, String)>) -> Result<()> {
+        for (hash_name, digest, resolved_hash) in hashers {
+            let stream_hash = hex::encode(&digest.finalize());
+
+            if resolved_hash.eq_ignore_ascii_case(&stream_hash) {
+                self.dbg("hash check success");
+            } else {
+                // TODO: skipping checks etc
+                // also pretty msg
+                bail!(
+                    "Mismatched hash!
                     Type: {hash_name}
                     Expected hash: {resolved_hash}
                     Real hash: {stream_hash}"
-                    );
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `url` rewritten by the `mirrors` table of the config, in order
+    pub fn mirrors_of(&self, url: &str) -> Vec<String> {
+        let schemeless = url.split_once("://").map_or(url, |(_, rest)| rest);
+
+        self.config
+            .mirrors
+            .iter()
+            .filter_map(|mirror| {
+                let rest = url
+                    .strip_prefix(&mirror.from)
+                    .or_else(|| schemeless.strip_prefix(&mirror.from))?;
+
+                // so `example.com` doesn't match `example.com.evil.net`
+                (mirror.from.ends_with('/') || rest.is_empty() || rest.starts_with(['/', '?']))
+                    .then(|| format!("{}{rest}", mirror.to))
+            })
+            .collect()
+    }
+
+    /// Every url `resolved` can be downloaded from: its own url and mirrors,
+    /// each followed by their rewrites from the `mirrors` table
+    pub fn download_urls(&self, resolved: &ResolvedFile) -> Vec<String> {
+        let mut urls: Vec<String> = vec![];
+
+        for url in std::iter::once(&resolved.url).chain(&resolved.mirrors) {
+            for url in std::iter::once(url.clone()).chain(self.mirrors_of(url)) {
+                if !urls.contains(&url) {
+                    urls.push(url);
                 }
             }
+        }
 
-            Ok(())
-        };
+        urls
+    }
+
+    /// Download `url` to `target`, checking it against the hashes of `resolved`
+    async fn download_url(
+        &self,
+        url: &str,
+        resolved: &ResolvedFile,
+        target: &Path,
+        progress_bar: &ProgressBar,
+    ) -> Result<()> {
+        progress_bar.set_style(ProgressStyle::with_template(
+            "{spinner:.blue} {prefix} {msg}...",
+        )?);
+        progress_bar.set_prefix(ProgressPrefix::Fetching);
+        progress_bar.set_message(resolved.filename.clone());
+
+        let response = self
+            .with_source_auth(self.http_client.get(url), url)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let content_length = response.content_length();
+
+        match (resolved.size, content_length) {
+            (Some(size), Some(len)) => {
+                if size != len {
+                    // TODO: pretty msg
+                    self.warn(format!(
+                        "content length is wrong! expected: {size}, actual: {len}"
+                    ));
+                }
+
+                progress_bar.set_length(len);
+            }
+            (Some(size), None) | (None, Some(size)) => progress_bar.set_length(size),
+            _ => {}
+        }
+
+        progress_bar.disable_steady_tick();
+        progress_bar.set_style(ProgressStyle::with_template(
+            "{prefix:.blue.bold} {msg} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})",
+        )?);
+        progress_bar.set_prefix(ProgressPrefix::Downloading);
+
+        let mut hashers = Self::resolved_hashers(resolved);
+        let mut file_writer = BufWriter::new(
+            File::create(target)
+                .await
+                .context(format!("Creating '{}'", target.to_string_lossy()))?,
+        );
+
+        let mut stream = response.bytes_stream();
+        while let Some(item) = stream.next().await {
+            let item = item?;
+
+            for (_, digest, _) in &mut hashers {
+                digest.update(&item);
+            }
+
+            tokio::io::copy(&mut item.as_ref(), &mut file_writer)
+                .await
+                .context("Writing downloaded chunk")?;
+
+            progress_bar.inc(item.len() as u64);
+        }
+
+        file_writer.flush().await?;
+
+        self.validate_hashes(hashers)
+    }
+
+    /// Like [`Self::download_resolved`] but also tells where the file came from.
+    /// The returned file always has a sha256 hash.
+    /// An existing file is only kept if it matches the hashes of `resolved`,
+    /// or the sha256 in `known` if there are none
+    #[allow(clippy::too_many_lines)]
+    pub async fn fetch_resolved(
+        &self,
+        resolved: ResolvedFile,
+        destination: PathBuf,
+        progress_bar: ProgressBar,
+        known: Option<&KnownFile>,
+    ) -> Result<(ResolvedFile, FileOrigin)> {
+        let progress_bar = progress_bar.with_finish(indicatif::ProgressFinish::AndClear);
+        progress_bar.set_style(ProgressStyle::with_template(
+            "{spinner:.blue} {prefix} {msg}...",
+        )?);
+        progress_bar.set_prefix(ProgressPrefix::Checking);
+        progress_bar.enable_steady_tick(Duration::from_millis(250));
+
+        let mut hashers = Self::resolved_hashers(&resolved);
 
         // dest. file path
         let file_path = destination.join(&resolved.filename);
@@ -154,7 +428,7 @@ impl App {
                     }
                     1 => {
                         self.notify(Prefix::SkippedWarning, progress_bar.message());
-                        return Ok(resolved);
+                        return Ok((resolved, FileOrigin::Existing));
                     }
                     2 => bail!(message),
                     _ => unreachable!(),
@@ -166,17 +440,31 @@ impl App {
                     true
                 };
 
-                // TODO: optionally check hashes for existing file
-
                 if size_matches {
-                    // file already there and is ok
-                    self.notify(Prefix::Skipped, progress_bar.message());
-
-                    return Ok(resolved);
+                    if let Some(checked) =
+                        Box::pin(self.check_existing(&resolved, &file_path, known)).await?
+                    {
+                        // file already there and is ok
+                        self.notify(Prefix::Skipped, progress_bar.message());
+                        return Ok((checked, FileOrigin::Existing));
+                    }
                 }
             }
         }
 
+        // artifacts are zips, so the jar is extracted into the cache again when it's missing
+        if let CacheStrategy::GithubArtifact { path } = &resolved.cache {
+            self.github().ensure_extracted(&resolved, path).await?;
+        }
+
+        // Some(Path) if file exists in cache
+        let cached_file_path = self.resolve_cached_file(&resolved.cache);
+
+        util::unlink(&file_path).context(format!(
+            "Removing old file at '{}'",
+            file_path.to_string_lossy()
+        ))?;
+
         let target_file = File::create(&file_path).await.context(format!(
             "Creating destination file at '{}'",
             file_path.to_string_lossy()
@@ -188,7 +476,7 @@ impl App {
             let _ = fs::remove_file(&file_path);
         });
 
-        if let Some((cached, cached_size)) = match &cached_file_path {
+        let origin = if let Some((cached, cached_size)) = match &cached_file_path {
             Some((cached, true)) => {
                 let cached_size = cached
                     .metadata()
@@ -231,7 +519,7 @@ impl App {
             while let Some(item) = stream.next().await {
                 let item = item?;
 
-                if let Some((_, ref mut digest, _)) = hasher {
+                for (_, digest, _) in &mut hashers {
                     digest.update(&item);
                 }
 
@@ -248,70 +536,66 @@ impl App {
                 progress_bar.inc(item.len() as u64);
             }
 
+            file_writer.flush().await?;
+
             // TODO: retry downloading if fails
-            validate_hash(hasher)?;
+            self.validate_hashes(hashers)?;
 
             progress_bar.finish_and_clear();
             self.notify(Prefix::Copied, &resolved.filename);
-        } else {
-            progress_bar.set_prefix(ProgressPrefix::Fetching);
-            progress_bar.set_message(resolved.filename.clone());
-
-            let response = self
-                .http_client
-                .get(&resolved.url)
-                .send()
-                .await?
-                .error_for_status()?;
-
-            let content_length = response.content_length();
-
-            match (resolved.size, content_length) {
-                (Some(size), Some(len)) => {
-                    if size != len {
-                        // TODO: pretty msg
-                        self.warn(format!(
-                            "content length is wrong! expected: {size}, actual: {len}"
-                        ));
-                    }
 
-                    progress_bar.set_length(len);
-                }
-                (Some(size), None) | (None, Some(size)) => progress_bar.set_length(size),
-                _ => {}
+            if matches!(resolved.cache, CacheStrategy::Local { .. }) {
+                FileOrigin::Local
+            } else {
+                FileOrigin::Cached
+            }
+        } else {
+            if let CacheStrategy::Local { path } = &resolved.cache {
+                bail!("The local file '{path}' is missing or doesn't match the lockfile");
             }
 
-            progress_bar.disable_steady_tick();
-            progress_bar.set_style(ProgressStyle::with_template(
-                "{prefix:.blue.bold} {msg} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})",
-            )?);
-            progress_bar.set_prefix(ProgressPrefix::Downloading);
+            // if file can be cached, download to the file in cache dir
+            // otherwise to the output file
+            let cache_target = cached_file_path.map(|(path, _exists)| path);
 
-            // if file can be cached, BufWriter to the file in cache dir
-            // otherwise BufWriter to output file
-            let mut file_writer = BufWriter::new(if let Some((path, _exists)) = cached_file_path {
-                tokio::fs::create_dir_all(path.parent().unwrap()).await?;
-                File::create(path).await?
-            } else {
-                target_file
+            // same as above but for the cache file, so a failed or cancelled
+            // download doesn't leave a partial file in the cache
+            let mut cache_bomb = Bomb(cache_target.is_some(), || {
+                if let Some(path) = &cache_target {
+                    let _ = fs::remove_file(path);
+                }
             });
 
-            let mut stream = response.bytes_stream();
-            while let Some(item) = stream.next().await {
-                let item = item?;
+            if let Some(path) = &cache_target {
+                tokio::fs::create_dir_all(path.parent().unwrap()).await?;
+            }
 
-                if let Some((_, ref mut digest, _)) = hasher {
-                    digest.update(&item);
-                }
+            // every attempt creates the file again
+            drop(target_file);
+            let target = cache_target.as_ref().unwrap_or(&file_path);
 
-                tokio::io::copy(&mut item.as_ref(), &mut file_writer)
+            let urls = self.download_urls(&resolved);
+            for (idx, url) in urls.iter().enumerate() {
+                match self
+                    .download_url(url, &resolved, target, &progress_bar)
                     .await
-                    .context("Writing downloaded chunk")?;
-
-                progress_bar.inc(item.len() as u64);
+                {
+                    Ok(()) => break,
+                    Err(err) if idx + 1 < urls.len() => {
+                        self.warn(format!(
+                            "Downloading {} from {url} failed, trying {}: {err:#}",
+                            resolved.filename,
+                            urls[idx + 1]
+                        ));
+                        progress_bar.reset();
+                    }
+                    Err(err) => {
+                        return Err(err.context(format!("Downloading {}", resolved.filename)))
+                    }
+                }
             }
 
-            validate_hash(hasher)?;
+            cache_bomb.defuse();
 
             // if we downloaded to cache instead of output above, copy the file to output
             // small todo: maybe write to both while downloading?
@@ -320,7 +604,9 @@ impl App {
                     self.get_cache(namespace).map(|c| c.path(path))
                 }
                 CacheStrategy::Indexed { .. } => todo!(),
-                CacheStrategy::None => None,
+                CacheStrategy::Local { .. }
+                | CacheStrategy::GithubArtifact { .. }
+                | CacheStrategy::None => None,
             } {
                 progress_bar.set_style(ProgressStyle::with_template(
                     "{spinner:.blue} {prefix} {msg}...",
@@ -332,13 +618,53 @@ impl App {
 
             progress_bar.finish_and_clear();
             self.notify(Prefix::Downloaded, &resolved.filename);
-        }
+
+            FileOrigin::Downloaded
+        };
+
+        let resolved = Self::with_sha256(resolved, &file_path, known).await?;
 
         // succeeded, so defuse
         bomb.defuse();
 
         progress_bar.finish_and_clear();
 
-        Ok(resolved)
+        Ok((resolved, origin))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{app::BaseApp, model::Mirror};
+
+    #[test]
+    fn finds_mirrors_of_urls() {
+        let mut app = BaseApp::new()
+            .unwrap()
+            .upgrade_with_default_server()
+            .unwrap();
+        app.config.mirrors = vec![
+            Mirror {
+                from: "cdn.modrinth.com".to_owned(),
+                to: "https://mirror.example.com/modrinth".to_owned(),
+            },
+            Mirror {
+                from: "https://repo.example.com/maven/".to_owned(),
+                to: "https://nexus.example.com/".to_owned(),
+            },
+        ];
+
+        assert_eq!(
+            app.mirrors_of("https://cdn.modrinth.com/data/a.jar"),
+            vec!["https://mirror.example.com/modrinth/data/a.jar"]
+        );
+        assert_eq!(
+            app.mirrors_of("https://repo.example.com/maven/a/b.jar"),
+            vec!["https://nexus.example.com/a/b.jar"]
+        );
+        assert!(app
+            .mirrors_of("https://cdn.modrinth.com.evil.net/data/a.jar")
+            .is_empty());
+        assert!(app.mirrors_of("https://example.com/a.jar").is_empty());
     }
 }
@@ -2,7 +2,7 @@ use std::{borrow::Cow, env, fmt::Display};
 
 use anyhow::Result;
 use console::{style, StyledObject};
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
 
 use crate::util::SelectItem;
 
@@ -233,4 +233,20 @@ impl App {
 
         Ok(item.0.clone())
     }
+
+    /// Pick any number of items, all of them are selected at first
+    pub fn multi_select<T: Clone>(&self, prompt: &str, items: &[SelectItem<T>]) -> Result<Vec<T>> {
+        let selected = self.multi_progress.suspend(|| {
+            MultiSelect::with_theme(&ColorfulTheme::default())
+                .items(items)
+                .with_prompt(prompt)
+                .defaults(&vec![true; items.len()])
+                .interact()
+        })?;
+
+        Ok(selected
+            .into_iter()
+            .map(|idx| items[idx].0.clone())
+            .collect())
+    }
 }
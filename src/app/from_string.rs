@@ -2,7 +2,10 @@ use std::{borrow::Cow, path::Path};
 
 use anyhow::{bail, Context, Result};
 
-use crate::{model::Downloadable, util::SelectItem};
+use crate::{
+    model::{DownloadOptions, Downloadable, VersionFilter},
+    util::SelectItem,
+};
 
 use super::App;
 
@@ -20,13 +23,41 @@ impl App {
                     Ok(Downloadable::Modrinth {
                         id: id.to_owned(),
                         version: version.to_owned(),
+                        channel: None,
+                        game_versions: vec![],
+                        options: DownloadOptions::default(),
                     })
                 }
-                ("cr" | "cf" | "curseforge" | "curserinth", id) => {
+                ("cr" | "curserinth", id) => {
                     let (id, version) = id.split_once(',').unwrap_or((id, "latest"));
                     Ok(Downloadable::CurseRinth {
                         id: id.to_owned(),
                         version: version.to_owned(),
+                        channel: None,
+                        game_versions: vec![],
+                        options: DownloadOptions::default(),
+                    })
+                }
+                // the official api needs a key, so they stay on curserinth without one
+                ("cf" | "curseforge", id) if self.config.sources.curseforge.api_key.is_some() => {
+                    let (id, version) = id.split_once(',').unwrap_or((id, "latest"));
+                    Ok(Downloadable::CurseForge {
+                        id: id.to_owned(),
+                        version: version.to_owned(),
+                        options: DownloadOptions::default(),
+                    })
+                }
+                ("cf" | "curseforge", id) => {
+                    self.warn(
+                        "No CurseForge API key is set, using CurseRinth. Set CURSEFORGE_API_KEY to use the official API",
+                    );
+                    let (id, version) = id.split_once(',').unwrap_or((id, "latest"));
+                    Ok(Downloadable::CurseRinth {
+                        id: id.to_owned(),
+                        version: version.to_owned(),
+                        channel: None,
+                        game_versions: vec![],
+                        options: DownloadOptions::default(),
                     })
                 }
                 ("hangar" | "h", id) => {
@@ -34,6 +65,9 @@ impl App {
                     Ok(Downloadable::Hangar {
                         id: id.to_owned(),
                         version: version.to_owned(),
+                        channel: None,
+                        game_versions: vec![],
+                        options: DownloadOptions::default(),
                     })
                 }
                 ("spigot" | "spiget", id) => {
@@ -41,6 +75,7 @@ impl App {
                     Ok(Downloadable::Spigot {
                         id: id.to_owned(),
                         version: version.to_owned(),
+                        options: DownloadOptions::default(),
                     })
                 }
                 ("ghrel" | "gh" | "github", id) => {
@@ -50,6 +85,18 @@ impl App {
                         repo: repo.to_owned(),
                         tag: tag.to_owned(),
                         asset: "first".to_owned(),
+                        options: DownloadOptions::default(),
+                    })
+                }
+                ("gitlab" | "gl", id) => {
+                    let (repo, tag) = id.split_once(',').unwrap_or((id, "latest"));
+
+                    Ok(Downloadable::Gitlab {
+                        url: None,
+                        repo: repo.to_owned(),
+                        tag: tag.to_owned(),
+                        asset: "first".to_owned(),
+                        options: DownloadOptions::default(),
                     })
                 }
                 (ty, _) => bail!("Unknown identifier '{ty}'"),
@@ -75,6 +122,9 @@ impl App {
                 Ok(Downloadable::Modrinth {
                     id: id.to_owned().to_owned(),
                     version: version.to_owned().to_owned(),
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
                 })
             }
 
@@ -111,6 +161,9 @@ impl App {
                 Ok(Downloadable::Modrinth {
                     id: id.to_owned().to_owned(),
                     version: version.clone(),
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
                 })
             }
 
@@ -118,7 +171,10 @@ impl App {
                 let version = if let ["version", v] = rest {
                     (*v).to_string()
                 } else {
-                    let (versions, _) = self.curserinth().fetch_versions(id).await?;
+                    let (versions, _) = self
+                        .curserinth()
+                        .fetch_versions(id, VersionFilter::default())
+                        .await?;
 
                     if versions.is_empty() {
                         bail!("No compatible versions found");
@@ -147,6 +203,46 @@ impl App {
                 Ok(Downloadable::CurseRinth {
                     id: (*id).to_string(),
                     version,
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
+                })
+            }
+
+            // https://www.curseforge.com/minecraft/{CLASS}/{SLUG}/files/{FILE}
+            (Some("www.curseforge.com" | "curseforge.com"), ["minecraft", _, id, rest @ ..])
+                if self.config.sources.curseforge.api_key.is_some() =>
+            {
+                let version = if let ["files" | "download", file, ..] = rest {
+                    (*file).to_string()
+                } else {
+                    let files = self.curseforge().fetch_versions(id).await?;
+
+                    self.select(
+                        "Select a version",
+                        &[SelectItem(
+                            "latest".to_owned(),
+                            Cow::Borrowed("Always use latest"),
+                        )]
+                        .into_iter()
+                        .chain(files.iter().map(|f| {
+                            SelectItem(
+                                f.id.to_string(),
+                                Cow::Owned(if f.display_name == f.file_name {
+                                    f.file_name.clone()
+                                } else {
+                                    format!("[{}] {}", f.file_name, f.display_name)
+                                }),
+                            )
+                        }))
+                        .collect::<Vec<_>>(),
+                    )?
+                };
+
+                Ok(Downloadable::CurseForge {
+                    id: (*id).to_string(),
+                    version,
+                    options: DownloadOptions::default(),
                 })
             }
 
@@ -157,7 +253,10 @@ impl App {
                 let version = if let [_, ver] = rest {
                     (*ver).to_string()
                 } else {
-                    let (versions, _) = self.curserinth().fetch_versions(&id).await?;
+                    let (versions, _) = self
+                        .curserinth()
+                        .fetch_versions(&id, VersionFilter::default())
+                        .await?;
 
                     if versions.is_empty() {
                         bail!("No compatible versions found");
@@ -183,15 +282,36 @@ impl App {
                     version.id.clone()
                 };
 
-                Ok(Downloadable::CurseRinth { id, version })
+                Ok(Downloadable::CurseRinth {
+                    id,
+                    version,
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
+                })
             }
 
             // https://www.spigotmc.org/resources/http-requests.101253/
             (Some("www.spigotmc.org"), ["resources", id]) => Ok(Downloadable::Spigot {
                 id: (*id).to_string(),
                 version: "latest".to_owned(),
+                options: DownloadOptions::default(),
             }),
 
+            // https://github.com/{owner}/{repo}/actions/workflows/{workflow}
+            (Some("github.com"), [owner, repo_name, "actions", "workflows", workflow]) => {
+                Ok(Downloadable::GithubActions {
+                    repo: format!("{owner}/{repo_name}"),
+                    workflow: (*workflow).to_string(),
+                    branch: None,
+                    status: "success".to_owned(),
+                    run: "latest".to_owned(),
+                    artifact: "first".to_owned(),
+                    file: "first".to_owned(),
+                    options: DownloadOptions::default(),
+                })
+            }
+
             // https://github.com/{owner}/{repo}/releases/{'tag'|'download'}/{tag}/{filename}
             (Some("github.com"), [owner, repo_name, rest @ ..]) => {
                 let repo = format!("{owner}/{repo_name}");
@@ -265,6 +385,50 @@ impl App {
                     repo,
                     tag: tag.to_string(),
                     asset,
+                    options: DownloadOptions::default(),
+                })
+            }
+
+            // https://gitlab.com/{group}/{project}/-/releases/{tag}/downloads/{filename}
+            (Some(domain), path) if path.windows(2).any(|w| w == ["-", "releases"]) => {
+                let split = path.iter().position(|s| *s == "-").unwrap_or_default();
+                let (project, rest) = path.split_at(split);
+
+                let (tag, asset) = match &rest[2..] {
+                    [tag, "downloads", .., filename] => {
+                        ((*tag).to_owned(), filename.replace(tag, "${tag}"))
+                    }
+                    [tag, ..] => ((*tag).to_owned(), "first".to_owned()),
+                    [] => ("latest".to_owned(), "first".to_owned()),
+                };
+
+                Ok(Downloadable::Gitlab {
+                    url: (domain != "gitlab.com").then(|| format!("{}://{domain}", url.scheme())),
+                    repo: project.join("/"),
+                    tag,
+                    asset,
+                    options: DownloadOptions::default(),
+                })
+            }
+
+            // https://codeberg.org/{owner}/{repo}/releases/{'tag'|'download'}/{tag}/{filename}
+            (Some(domain), [owner, repo_name, "releases", rest @ ..])
+                if self.gitea().is_instance(domain) =>
+            {
+                let (tag, asset) = match rest {
+                    ["tag" | "download", tag, filename, ..] => {
+                        ((*tag).to_owned(), filename.replace(tag, "${tag}"))
+                    }
+                    ["tag" | "download", tag] => ((*tag).to_owned(), "first".to_owned()),
+                    _ => ("latest".to_owned(), "first".to_owned()),
+                };
+
+                Ok(Downloadable::Gitea {
+                    url: format!("{}://{domain}", url.scheme()),
+                    repo: format!("{owner}/{repo_name}"),
+                    tag,
+                    asset,
+                    options: DownloadOptions::default(),
                 })
             }
 
@@ -309,6 +473,7 @@ impl App {
                             url: urlstr.to_owned(),
                             filename: Some(input),
                             desc: if desc.is_empty() { None } else { Some(desc) },
+                            options: DownloadOptions::default(),
                         })
                     }
                     1 => {
@@ -358,6 +523,9 @@ impl App {
                             job,
                             build,
                             artifact,
+                            branch: None,
+                            result: "success".to_owned(),
+                            options: DownloadOptions::default(),
                         })
                     }
                     2 => {
@@ -463,6 +631,9 @@ impl App {
                             artifact,
                             version,
                             filename,
+                            classifier: None,
+                            extension: None,
+                            options: DownloadOptions::default(),
                         })
                     }
                     _ => unreachable!(),
@@ -2,7 +2,11 @@ use anyhow::{Context, Result};
 use digest::{Digest, DynDigest};
 use indicatif::ProgressBar;
 use sha2::Sha256;
-use std::{collections::HashMap, marker::Unpin, path::PathBuf};
+use std::{
+    collections::HashMap,
+    marker::Unpin,
+    path::{Path, PathBuf},
+};
 use tokio::{
     fs::File,
     io::{AsyncRead, AsyncWrite},
@@ -94,6 +98,21 @@ impl App {
         Ok(hex::encode(&digester.finalize()))
     }
 
+    /// Hash the file at `path` with the algorithm `hash_name` (see [`Self::create_hasher`])
+    pub async fn hash_file(path: &Path, hash_name: &str) -> Result<String> {
+        let mut file = File::open(path)
+            .await
+            .context(format!("Opening file '{}'", path.display()))?;
+
+        Self::copy_with_hashing(
+            &mut file,
+            &mut tokio::io::sink(),
+            Self::create_hasher(hash_name),
+        )
+        .await
+        .context(format!("Hashing '{}'", path.display()))
+    }
+
     pub fn hash_sha256(contents: &str) -> String {
         let mut hasher = Sha256::new();
 
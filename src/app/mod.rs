@@ -6,13 +6,17 @@ mod from_string;
 mod hashing;
 mod progress;
 mod resolvable;
+mod steps;
 
 use anyhow::{Context, Result};
 pub use caching::*;
 use confique::Config;
+pub use downloading::{FileOrigin, KnownFile};
 pub use feedback::*;
 use indicatif::MultiProgress;
 pub use resolvable::*;
+use serde::Serialize;
+pub use steps::*;
 
 use crate::model::{AppConfig, Downloadable, Network, Server};
 use crate::sources;
@@ -30,7 +34,8 @@ pub const APP_USER_AGENT: &str = concat!(
     env!("CARGO_PKG_REPOSITORY"),
 );
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum AddonType {
     Plugin,
     Mod,
@@ -84,6 +89,8 @@ pub struct App {
     pub http_client: reqwest::Client,
     pub server: Server,
     pub network: Option<Network>,
+    /// The `--profile`, applied again when server.toml is reloaded
+    pub profile: Option<String>,
 
     pub multi_progress: MultiProgress,
     pub config: AppConfig,
@@ -95,6 +102,7 @@ impl App {
             http_client: base_app.http_client,
             server: Server::default(),
             network: Network::load()?,
+            profile: None,
             multi_progress: MultiProgress::new(),
             config: Config::builder()
                 .env()
@@ -112,8 +120,23 @@ impl App {
         self.server.mc_version.as_str()
     }
 
+    /// Apply the profile `name` to the server, and again whenever it's reloaded
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        self.server.apply_profile(name)?;
+        self.profile = Some(name.to_owned());
+        Ok(())
+    }
+
     pub fn reload_server(&mut self) -> Result<()> {
-        self.server = Server::load().context("Loading server.toml")?;
+        self.server = Server::load_from(&self.server.path.join("server.toml"))
+            .context("Loading server.toml")?;
+
+        if let Some(profile) = &self.profile {
+            self.server
+                .apply_profile(profile)
+                .context(format!("Applying the profile '{profile}'"))?;
+        }
+
         Ok(())
     }
 
@@ -287,6 +310,11 @@ impl App {
         })
     }
 
+    /// Amount of downloads that can run concurrently, never zero
+    pub fn parallel_downloads(&self) -> usize {
+        self.config.parallel_downloads.max(1)
+    }
+
     pub fn get_cache(&self, ns: &str) -> Option<Cache> {
         if self.config.disable_cache.iter().any(|s| s.as_str() == ns) {
             None
@@ -334,6 +362,9 @@ impl App {
         jenkins => JenkinsAPI,
         modrinth => ModrinthAPI,
         curserinth => CurserinthAPI,
+        curseforge => CurseForgeAPI,
+        gitlab => GitlabAPI,
+        gitea => GiteaAPI,
         neoforge => NeoforgeAPI,
         forge => ForgeAPI,
         fabric => FabricAPI,
@@ -356,3 +387,47 @@ impl App {
         mrpack => MRPackInterop,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::Builder;
+
+    use super::*;
+
+    #[test]
+    fn reloading_keeps_the_profile() {
+        let dir = Builder::new()
+            .prefix("mcman-profile-test")
+            .tempdir()
+            .unwrap();
+        std::fs::write(
+            dir.path().join("server.toml"),
+            r#"
+name = "test"
+mc_version = "1.20.4"
+
+[jar]
+type = "paper"
+
+[variables]
+MODE = "survival"
+
+[profiles.creative.variables]
+MODE = "creative"
+"#,
+        )
+        .unwrap();
+
+        let mut app = BaseApp::new()
+            .unwrap()
+            .upgrade_with_default_server()
+            .unwrap();
+        app.server = Server::load_from(&dir.path().join("server.toml")).unwrap();
+        app.apply_profile("creative").unwrap();
+        assert_eq!(app.var("MODE").as_deref(), Some("creative"));
+
+        app.reload_server().unwrap();
+        assert_eq!(app.server.profile.as_deref(), Some("creative"));
+        assert_eq!(app.var("MODE").as_deref(), Some("creative"));
+    }
+}
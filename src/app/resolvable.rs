@@ -16,6 +16,14 @@ pub struct ResolvedFile {
     pub cache: CacheStrategy,
     pub size: Option<u64>,
     pub hashes: HashMap<String, String>,
+    /// The concrete version a version selector like `latest` or `^5.2` resolved to
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Other urls serving the same file, tried in order when `url` fails
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -30,6 +38,11 @@ pub enum CacheStrategy {
         key: String,
         value: String,
     },
+    /// A file on disk like a `file` addon, `path` is absolute or relative to server.toml
+    Local { path: String },
+    /// A jar in a GitHub Actions artifact, which is a zip. Extracted again to
+    /// `path` in the cache when it's missing, the url is the one of the zip
+    GithubArtifact { path: String },
     #[default]
     None,
 }
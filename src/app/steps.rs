@@ -1,10 +1,22 @@
-use std::path::PathBuf;
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
 
-use anyhow::Result;
-use indicatif::ProgressBar;
+use anyhow::{bail, Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use pathdiff::diff_paths;
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::Command,
+};
 
-use super::{ResolvedFile, App};
+use super::{App, FileOrigin, KnownFile, ResolvedFile};
 
+/// A single unit of work, executed with [`App::run_step`]
 #[derive(Debug, Clone)]
 pub enum Step {
     Download(DownloadTask),
@@ -12,31 +24,176 @@ pub enum Step {
         jar: String,
         args: Vec<String>,
         path: PathBuf,
-    }
+        /// Shown while running, e.g. `Forge Installer`
+        label: String,
+        /// Short name for the log file, e.g. `fi`
+        tag: String,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub struct DownloadTask {
     pub resolved_file: ResolvedFile,
     pub path: PathBuf,
+    /// What the file was when it was last downloaded, to check an existing
+    /// file with when the source has no hashes
+    pub known: Option<KnownFile>,
+}
+
+impl Step {
+    /// Downloads can fail because of the network, so they're retried
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Download(_))
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Download(task) => write!(f, "Download {}", task.resolved_file.filename),
+            Self::ExecuteJava { label, .. } => write!(f, "Run {label}"),
+        }
+    }
 }
 
 impl App {
-    pub async fn run_step(&self, step: &Step) -> Result<()> {
+    /// Run a step, retrying it up to `step_retries` times if it can be retried.
+    /// For downloads, returns the file (with its sha256) and where it came from
+    pub async fn run_step(
+        &self,
+        step: &Step,
+        progress_bar: ProgressBar,
+    ) -> Result<Option<(ResolvedFile, FileOrigin)>> {
+        let retries = if step.is_retryable() {
+            self.config.step_retries
+        } else {
+            0
+        };
+
+        let mut attempt = 0;
+        loop {
+            match self.try_step(step, progress_bar.clone()).await {
+                Err(e) if attempt < retries => {
+                    attempt += 1;
+                    self.warn(format!(
+                        "{step} failed, retrying ({attempt}/{retries}): {e:#}"
+                    ));
+                    tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
+                }
+                res => break res.context(step.to_string()),
+            }
+        }
+    }
+
+    async fn try_step(
+        &self,
+        step: &Step,
+        progress_bar: ProgressBar,
+    ) -> Result<Option<(ResolvedFile, FileOrigin)>> {
         match step {
-            Step::Download(folder, resolved_file) => {
-                self.download_resolved(
-                    resolved_file.clone(),
-                    folder.clone(),
-                    self.multi_progress.add(ProgressBar::new_spinner())
-                ).await?;
+            Step::Download(DownloadTask {
+                resolved_file,
+                path,
+                known,
+            }) => {
+                let fetched = self
+                    .fetch_resolved(
+                        resolved_file.clone(),
+                        path.clone(),
+                        progress_bar,
+                        known.as_ref(),
+                    )
+                    .await?;
+
+                Ok(Some(fetched))
             }
 
-            Step::ExecuteJava { jar, args, path } => {
-                todo!();
+            Step::ExecuteJava {
+                jar,
+                args,
+                path,
+                label,
+                tag,
+            } => {
+                let mut cmd_args = vec!["-jar", jar.as_str()];
+                cmd_args.extend(args.iter().map(String::as_str));
+
+                self.execute_child((&self.config.default_java, &cmd_args), path, label, tag)
+                    .await
+                    .context(format!(
+                        "Executing command: '{} {}'",
+                        self.config.default_java,
+                        cmd_args.join(" ")
+                    ))?;
+
+                Ok(None)
             }
         }
-        
+    }
+
+    pub async fn execute_child(
+        &self,
+        cmd: (&str, &[&str]),
+        path: &Path,
+        label: &str,
+        tag: &str,
+    ) -> Result<()> {
+        // because jre cant understand UNC
+        let dir = diff_paths(path, std::env::current_dir()?.canonicalize()?).unwrap();
+
+        let args: Vec<String> = cmd.1.iter().map(|a| self.server.format(a)).collect();
+        self.dbg(args.join(" "));
+
+        let mut child = Command::new(cmd.0)
+            .args(args)
+            .current_dir(dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context(format!("Running {label}"))?;
+
+        let spinner = self
+            .multi_progress
+            .add(
+                ProgressBar::new_spinner().with_style(ProgressStyle::with_template(
+                    "    {spinner:.green} {prefix:.bold} {msg}",
+                )?),
+            );
+
+        spinner.enable_steady_tick(Duration::from_millis(200));
+        spinner.set_prefix(format!("[{tag}]"));
+
+        let mut log_file = File::create(path.join(format!(".{tag}.mcman.log"))).await?;
+
+        log_file
+            .write_all(format!("=== mcman {tag} / {label} output ===\n\n").as_bytes())
+            .await?;
+
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        while let Some(buf) = lines
+            .next_line()
+            .await
+            .context("Reading child process stdout buffer")?
+        {
+            let buf = buf.trim();
+
+            if !buf.is_empty() {
+                log_file.write_all(buf.as_bytes()).await?;
+                log_file.write_all(b"\n").await?;
+
+                if let Some(last_line) = buf.split('\n').last() {
+                    spinner.set_message(last_line.to_string());
+                }
+            }
+        }
+
+        if !child.wait().await?.success() {
+            bail!("{label} exited with non-zero code");
+        }
+
+        spinner.disable_steady_tick();
+        spinner.finish_and_clear();
+
         Ok(())
     }
 }
@@ -1,9 +1,10 @@
 use anyhow::{bail, Context, Result};
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashSet};
 
 use crate::{
-    app::{App, Prefix},
-    model::Downloadable,
+    app::{AddonType, App, Prefix},
+    model::{DownloadOptions, Downloadable, Lockfile},
+    sources::modrinth::{ModrinthProject, ModrinthVersion},
     util::SelectItem,
 };
 
@@ -12,6 +13,7 @@ pub struct Args {
     search: Option<String>,
 }
 
+#[allow(clippy::too_many_lines)]
 pub async fn run(mut app: App, args: Args) -> Result<()> {
     let search_type = app.select(
         "Which project type?",
@@ -96,10 +98,19 @@ pub async fn run(mut app: App, args: Args) -> Result<()> {
             todo!("Modpack importing currently unsupported")
         }
         "mod" => {
-            app.add_addon_inferred(Downloadable::Modrinth {
-                id: project.slug.clone(),
-                version: version.id.clone(),
-            })?;
+            let addon_type = app.infer_addon_type()?;
+            app.add_addon(
+                addon_type,
+                Downloadable::Modrinth {
+                    id: project.slug.clone(),
+                    version: version.id.clone(),
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
+                },
+            )?;
+
+            add_dependencies(&mut app, addon_type, &project, &version).await?;
 
             app.save_changes()?;
             app.notify(Prefix::Imported, format!("{} from modrinth", project.title));
@@ -109,6 +120,9 @@ pub async fn run(mut app: App, args: Args) -> Result<()> {
             app.add_datapack(Downloadable::Modrinth {
                 id: project.slug.clone(),
                 version: version.id.clone(),
+                channel: None,
+                game_versions: vec![],
+                options: DownloadOptions::default(),
             })?;
 
             app.save_changes()?;
@@ -119,3 +133,101 @@ pub async fn run(mut app: App, args: Args) -> Result<()> {
 
     Ok(())
 }
+
+/// Offer to add the missing required dependencies of `version` to server.toml
+async fn add_dependencies(
+    app: &mut App,
+    addon_type: AddonType,
+    project: &ModrinthProject,
+    version: &ModrinthVersion,
+) -> Result<()> {
+    let api = app.modrinth();
+
+    let mut installed = app
+        .server
+        .plugins
+        .iter()
+        .chain(&app.server.mods)
+        .filter_map(|addon| match addon {
+            Downloadable::Modrinth { id, .. } => Some(id.clone()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    // addons from other sources are found by the files of the last build
+    let output_dir = app.server.output_dir();
+    let lockfile = Lockfile::get_lockfile(&output_dir)?;
+    let files = [
+        (AddonType::Plugin, &lockfile.plugins),
+        (AddonType::Mod, &lockfile.mods),
+    ]
+    .into_iter()
+    .flat_map(|(ty, list)| {
+        let folder = output_dir.join(ty.folder());
+        list.iter()
+            .map(move |(_, resolved)| folder.join(&resolved.filename))
+    })
+    .filter(|path| path.is_file())
+    .collect::<Vec<_>>();
+    match api.versions_of_files(&files).await {
+        Ok(versions) => installed.extend(versions.into_iter().flatten().map(|v| v.project_id)),
+        Err(err) => app.warn(format!(
+            "Couldn't look up the built addons on Modrinth: {err:#}"
+        )),
+    }
+
+    let result = api
+        .resolve_dependencies(&[(project.title.clone(), version.clone())], &installed)
+        .await
+        .context("Resolving dependencies")?;
+
+    for (addon, incompatible) in &result.conflicts {
+        app.warn(format!("{addon} is incompatible with {incompatible}"));
+    }
+
+    if result.missing.is_empty() {
+        return Ok(());
+    }
+
+    let items = result
+        .missing
+        .iter()
+        .map(|dep| {
+            SelectItem(
+                dep.clone(),
+                Cow::Owned(format!(
+                    "{} [{}] - required by {}",
+                    dep.project.title, dep.version.version_number, dep.required_by
+                )),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let selected = app.multi_select("Add required dependencies?", &items)?;
+
+    for dep in &result.missing {
+        if selected.iter().any(|s| s.project.slug == dep.project.slug) {
+            app.add_addon(
+                addon_type,
+                Downloadable::Modrinth {
+                    id: dep.project.slug.clone(),
+                    version: dep.version.id.clone(),
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
+                },
+            )?;
+            app.notify(
+                Prefix::Imported,
+                format!("{} from modrinth", dep.project.title),
+            );
+        } else if app.server.options.resolve_dependencies {
+            app.info(format!(
+                "{} will be downloaded as a dependency when building",
+                dep.project.title
+            ));
+        }
+    }
+
+    Ok(())
+}
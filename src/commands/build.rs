@@ -1,10 +1,15 @@
 use std::path::PathBuf;
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 
-use crate::{app::App, core::BuildContext, model::Lockfile};
+use crate::{
+    app::App,
+    core::{report::BuildReport, BuildContext},
+    model::Lockfile,
+};
 
 #[derive(clap::Args)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct BuildArgs {
     /// The output directory for the server
     #[arg(short, long, value_name = "file")]
@@ -15,31 +20,59 @@ pub struct BuildArgs {
     #[arg(long)]
     /// Don't skip downloading already downloaded jars
     force: bool,
+    /// Download exactly what the lockfile has, without resolving anything
+    #[arg(long)]
+    locked: bool,
+    /// Build in a staging directory and only replace the output if everything succeeds
+    #[arg(long)]
+    atomic: bool,
+    /// Write a JSON report of the build to this file
+    #[arg(long, value_name = "file")]
+    report: Option<PathBuf>,
+    /// Append a summary of the build to the GitHub Actions step summary
+    #[arg(long)]
+    github_summary: bool,
 }
 
 impl BuildArgs {
-    pub fn create_build_context(self, app: &mut App) -> Result<BuildContext<'_>> {
-        let default_output = app.server.path.join("server");
-        let output_dir = self.output.unwrap_or(default_output);
+    pub fn create_build_context(self, app: &mut App) -> BuildContext<'_> {
+        let output_dir = self.output.unwrap_or_else(|| app.server.output_dir());
 
-        std::fs::create_dir_all(&output_dir).context("Failed to create output directory")?;
-
-        Ok(BuildContext {
+        BuildContext {
             app,
             force: self.force,
+            locked: self.locked,
+            atomic: self.atomic,
+            live_output_dir: None,
+            report: BuildReport::default(),
+            report_path: self.report,
+            github_summary: self.github_summary,
             skip_stages: self.skip,
             output_dir,
             lockfile: Lockfile::default(),
             new_lockfile: Lockfile::default(),
             server_process: None,
-        })
+        }
     }
 }
 
-pub async fn run(mut app: App, args: BuildArgs) -> Result<()> {
-    let mut ctx = args.create_build_context(&mut app)?;
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    build_args: BuildArgs,
+    /// Only show what would change compared to the lockfile, without writing anything
+    #[arg(long)]
+    dry_run: bool,
+}
 
-    ctx.build_all().await?;
+pub async fn run(mut app: App, args: Args) -> Result<()> {
+    let mut ctx = args.build_args.create_build_context(&mut app);
+
+    if args.dry_run {
+        ctx.plan().await?;
+    } else {
+        ctx.build_all().await?;
+    }
 
     Ok(())
 }
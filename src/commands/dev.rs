@@ -33,7 +33,7 @@ impl DevArgs {
             cfg
         };
 
-        let mut dev_session = self.run_args.create_dev_session(app)?;
+        let mut dev_session = self.run_args.create_dev_session(app);
         dev_session.hot_reload = Some(Arc::new(Mutex::new(config)));
         // no.
         dev_session.test_mode = false;
@@ -44,7 +44,7 @@ impl DevArgs {
 
 pub async fn run(mut app: App, args: DevArgs) -> Result<()> {
     let dev_session = args.create_dev_session(&mut app)?;
-    dev_session.start().await?;
+    Box::pin(dev_session.start()).await?;
 
     println!();
 
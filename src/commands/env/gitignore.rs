@@ -3,7 +3,7 @@ use anyhow::Result;
 use crate::{app::App, util::env::write_git};
 
 pub fn run(app: &App) -> Result<()> {
-    write_git()?;
+    write_git(&app.server.output_dir_names())?;
 
     app.success("Configured gitignore and gitattributes");
 
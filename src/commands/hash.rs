@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Result};
+use console::style;
+use futures_util::{stream, StreamExt};
+use indicatif::ProgressBar;
+use tempfile::Builder;
+
+use crate::{
+    app::{AddonType, App, CacheStrategy, ResolvedFile},
+    model::Downloadable,
+};
+
+pub async fn run(mut app: App) -> Result<()> {
+    let mut pinned = vec![];
+    for addon_type in [AddonType::Plugin, AddonType::Mod] {
+        for (idx, hash) in find_hashes(&app, addon_type).await {
+            pinned.push((addon_type, idx, hash));
+        }
+    }
+
+    if pinned.is_empty() {
+        app.success("Every addon already has a hash");
+        return Ok(());
+    }
+
+    for (addon_type, idx, hash) in &pinned {
+        let addon = match addon_type {
+            AddonType::Plugin => &mut app.server.plugins[*idx],
+            AddonType::Mod => &mut app.server.mods[*idx],
+        };
+        addon.options_mut().hash = Some(hash.clone());
+        let name = addon.to_short_string();
+
+        app.log(format!(
+            "{} {name} {}",
+            style(addon_type.to_string()).dim(),
+            style(hash).dim()
+        ));
+    }
+
+    app.save_changes()?;
+    app.success(format!(
+        "Pinned {} hash{}",
+        pinned.len(),
+        if pinned.len() == 1 { "" } else { "es" }
+    ));
+
+    Ok(())
+}
+
+/// Hashes for the addons in server.toml without one as (index, hash).
+/// Addons that fail to resolve or download are skipped with a warning, and floating
+/// versions are skipped since their next release wouldn't match the hash
+async fn find_hashes(app: &App, addon_type: AddonType) -> Vec<(usize, String)> {
+    let list: &[Downloadable] = match addon_type {
+        AddonType::Plugin => &app.server.plugins,
+        AddonType::Mod => &app.server.mods,
+    };
+
+    let unpinned = list
+        .iter()
+        .enumerate()
+        .filter(|(_, addon)| addon.options().hash.is_none())
+        .filter(|(_, addon)| {
+            if addon.is_floating() {
+                app.warn(format!(
+                    "Not pinning {}, it doesn't have a fixed version",
+                    addon.to_short_string()
+                ));
+            }
+            !addon.is_floating()
+        })
+        .collect::<Vec<_>>();
+
+    stream::iter(unpinned.into_iter().map(|(idx, addon)| async move {
+        let progress_bar = app.multi_progress.add(ProgressBar::new_spinner());
+
+        let hash = match app.resolve(addon, &progress_bar).await {
+            Ok(resolved) => pinnable_hash(app, &resolved).await,
+            Err(e) => Err(e),
+        };
+
+        progress_bar.finish_and_clear();
+
+        match hash {
+            Ok(hash) => Some((idx, hash)),
+            Err(e) => {
+                app.warn(format!(
+                    "Couldn't hash {}, skipping: {e:#}",
+                    addon.to_short_string()
+                ));
+                None
+            }
+        }
+    }))
+    .buffered(app.parallel_downloads())
+    .filter_map(|hash| async move { hash })
+    .collect()
+    .await
+}
+
+/// The sha512 or sha256 of the source, or the sha256 of a fresh download.
+/// Files downloaded earlier (in the output or the cache) aren't used, they could
+/// have changed since
+async fn pinnable_hash(app: &App, resolved: &ResolvedFile) -> Result<String> {
+    if let Some((name, hash)) = ["sha512", "sha256"]
+        .into_iter()
+        .find_map(|name| resolved.hashes.get_key_value(name))
+    {
+        return Ok(format!("{name}:{hash}"));
+    }
+
+    let tmp_dir = Builder::new().prefix("mcman-hash").tempdir()?;
+    let resolved = ResolvedFile {
+        cache: match &resolved.cache {
+            CacheStrategy::File { .. } | CacheStrategy::Indexed { .. } => CacheStrategy::None,
+            cache => cache.clone(),
+        },
+        ..resolved.clone()
+    };
+
+    let downloaded = app
+        .download_resolved(
+            resolved,
+            tmp_dir.path().to_owned(),
+            ProgressBar::new_spinner(),
+        )
+        .await?;
+    let hash = downloaded
+        .hashes
+        .get("sha256")
+        .ok_or(anyhow!("Couldn't hash {}", downloaded.filename))?;
+
+    Ok(format!("sha256:{hash}"))
+}
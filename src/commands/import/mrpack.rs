@@ -4,7 +4,11 @@ use anyhow::Result;
 use indicatif::ProgressBar;
 use tempfile::Builder;
 
-use crate::{app::App, interop::mrpack::MRPackReader, model::Downloadable};
+use crate::{
+    app::App,
+    interop::mrpack::MRPackReader,
+    model::{DownloadOptions, Downloadable},
+};
 use std::fs::File;
 
 #[derive(clap::Args, Default)]
@@ -27,6 +31,7 @@ pub async fn run(mut app: App, args: Args) -> Result<()> {
                 url: src,
                 filename: None,
                 desc: None,
+                options: DownloadOptions::default(),
             }
         } else {
             app.dl_from_string(&src).await?
@@ -1,11 +1,12 @@
+use crate::app::App;
+use crate::model::Downloadable;
 use crate::util::md::MarkdownTable;
-use crate::{app::App, model::Server};
-use anyhow::{Context, Result};
 use console::style;
 use indexmap::IndexMap;
+use pathdiff::diff_paths;
 
-pub fn run(app: &App) -> Result<()> {
-    let server = Server::load().context("Failed to load server.toml")?;
+pub fn run(app: &App) {
+    let server = &app.server;
 
     let table = app.markdown().table_server();
 
@@ -48,6 +49,7 @@ pub fn run(app: &App) -> Result<()> {
         let text = table.render_ascii();
 
         println!("{text}");
+        print_skipped(app, &server.plugins, pad_keys);
     }
 
     if !server.mods.is_empty() {
@@ -67,7 +69,60 @@ pub fn run(app: &App) -> Result<()> {
         let text = table.render_ascii();
 
         println!("{text}");
+        print_skipped(app, &server.mods, pad_keys);
     }
 
-    Ok(())
+    if !server.extends.is_empty() {
+        println!(
+            " {:pad_keys$}> {} {}",
+            "",
+            style("Inherited").cyan(),
+            style("values").bold(),
+        );
+
+        let mut table = MarkdownTable::with_headers(vec!["Value".into(), "From".into()]);
+
+        let own_file = server.path.join("server.toml");
+        for (key, file) in &server.origins {
+            if file == &own_file {
+                continue;
+            }
+
+            let from = diff_paths(file, &server.path).unwrap_or(file.clone());
+            table
+                .rows
+                .push(vec![key.clone(), from.display().to_string()]);
+        }
+
+        println!("{}", table.render_ascii());
+    }
+}
+
+/// List the addons whose `when` doesn't match the server
+fn print_skipped(app: &App, list: &[Downloadable], pad_keys: usize) {
+    let skipped = list
+        .iter()
+        .filter_map(|addon| {
+            let reason = match addon.skip_reason(app) {
+                Ok(reason) => reason?,
+                Err(e) => format!("{e:#}"),
+            };
+            Some((addon.to_short_string(), reason))
+        })
+        .collect::<Vec<_>>();
+
+    if skipped.is_empty() {
+        return;
+    }
+
+    println!(
+        " {:pad_keys$}> {} {}",
+        "",
+        style(skipped.len()).bold(),
+        style("Skipped").yellow(),
+    );
+
+    for (name, reason) in skipped {
+        println!("   {name}: {}", style(reason).dim());
+    }
 }
@@ -262,7 +262,7 @@ pub async fn run(base_app: BaseApp, args: Args) -> Result<()> {
         }
     }
 
-    initialize_environment()?;
+    initialize_environment(&app.server.output_dir_names())?;
 
     if matches!(ty, InitType::Network) {
         println!(
@@ -288,10 +288,10 @@ pub async fn run(base_app: BaseApp, args: Args) -> Result<()> {
     Ok(())
 }
 
-pub fn initialize_environment() -> Result<()> {
+pub fn initialize_environment(output_dirs: &[String]) -> Result<()> {
     let theme = ColorfulTheme::default();
 
-    if write_git().is_err() {
+    if write_git(output_dirs).is_err() {
         println!(
             "{} {}{}{}",
             theme.prompt_prefix,
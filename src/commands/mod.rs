@@ -6,12 +6,16 @@ pub mod download;
 pub mod eject;
 pub mod env;
 pub mod export;
+pub mod hash;
 pub mod import;
 pub mod info;
 pub mod init;
 pub mod markdown;
 pub mod pull;
+pub mod rollback;
 pub mod run;
+pub mod update;
+pub mod verify;
 pub mod version;
 pub mod world;
 
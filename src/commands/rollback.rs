@@ -0,0 +1,42 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{app::App, core::staging::sibling_dir};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The output directory for the server
+    #[arg(short, long, value_name = "file")]
+    output: Option<PathBuf>,
+}
+
+pub fn run(app: &App, args: Args) -> Result<()> {
+    let output_dir = args.output.unwrap_or_else(|| app.server.output_dir());
+    let previous = sibling_dir(&output_dir, "previous")?;
+    let swap = sibling_dir(&output_dir, "rollback")?;
+
+    if !previous.exists() {
+        bail!(
+            "There's no previous build at '{}', only builds with --atomic keep one",
+            previous.display()
+        );
+    }
+
+    if output_dir.exists() {
+        fs::rename(&output_dir, &swap).context("Moving current build aside")?;
+    }
+
+    if let Err(e) = fs::rename(&previous, &output_dir) {
+        let _ = fs::rename(&swap, &output_dir);
+        return Err(e).context("Moving previous build into place");
+    }
+
+    if swap.exists() {
+        fs::rename(&swap, &previous).context("Keeping the rolled back build")?;
+    }
+
+    app.success("Rolled back to the previous build, run this again to undo");
+
+    Ok(())
+}
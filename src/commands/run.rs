@@ -14,21 +14,21 @@ pub struct RunArgs {
 }
 
 impl RunArgs {
-    pub fn create_dev_session(self, app: &mut App) -> Result<DevSession<'_>> {
-        let builder = self.build_args.create_build_context(app)?;
+    pub fn create_dev_session(self, app: &mut App) -> DevSession<'_> {
+        let builder = self.build_args.create_build_context(app);
 
-        Ok(DevSession {
+        DevSession {
             builder,
             jar_name: None,
             hot_reload: None,
             test_mode: self.test,
-        })
+        }
     }
 }
 
 pub async fn run(mut app: App, args: RunArgs) -> Result<()> {
-    let dev_session = args.create_dev_session(&mut app)?;
-    dev_session.start().await?;
+    let dev_session = args.create_dev_session(&mut app);
+    Box::pin(dev_session.start()).await?;
 
     println!();
 
@@ -0,0 +1,190 @@
+use std::borrow::Cow;
+
+use anyhow::{bail, Result};
+use console::style;
+use futures_util::{stream, StreamExt};
+
+use crate::{
+    app::{AddonType, App, Resolvable},
+    model::{AddonUpdate, Downloadable},
+    util::SelectItem,
+};
+
+/// Lines of changelog shown for each update
+const CHANGELOG_LINES: usize = 8;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Update everything without asking
+    #[arg(long)]
+    all: bool,
+    /// Only list the updates, exits with an error if there are any
+    #[arg(long, conflicts_with = "all")]
+    check: bool,
+    /// Don't show changelogs
+    #[arg(long)]
+    no_changelog: bool,
+}
+
+pub async fn run(mut app: App, args: Args) -> Result<()> {
+    app.print_job(&format!(
+        "{} {} plugins and {} mods...",
+        style("Checking").bold(),
+        app.server.plugins.len(),
+        app.server.mods.len(),
+    ));
+
+    let mut updates = vec![];
+    for addon_type in [AddonType::Plugin, AddonType::Mod] {
+        for (idx, update) in find_updates(&app, addon_type).await {
+            updates.push((addon_type, idx, update));
+        }
+    }
+
+    if updates.is_empty() {
+        app.success("Everything is up to date");
+        return Ok(());
+    }
+
+    for (addon_type, _, update) in &updates {
+        app.log(format!(
+            "{} {} {} -> {}{}",
+            style(addon_type.to_string()).dim(),
+            update.addon.to_short_string(),
+            style(&update.old_version).dim(),
+            style(&update.new_version).green(),
+            if update.is_pin() {
+                style(" (pin)").dim().to_string()
+            } else {
+                String::new()
+            },
+        ));
+
+        if let Some(changelog) = update.changelog.as_ref().filter(|_| !args.no_changelog) {
+            print_changelog(&app, changelog);
+        }
+    }
+
+    if args.check {
+        let upgrades = updates.iter().filter(|(_, _, u)| !u.is_pin()).count();
+        if upgrades != 0 {
+            bail!(
+                "{upgrades} update{} available",
+                if upgrades == 1 { "" } else { "s" }
+            );
+        }
+
+        app.success("Everything is up to date, `latest` versions can be pinned");
+        return Ok(());
+    }
+
+    let selected = if args.all {
+        updates
+    } else {
+        let items = updates
+            .into_iter()
+            .map(|(addon_type, idx, update)| {
+                let label = format!(
+                    "{} {} -> {}",
+                    update.addon.to_short_string(),
+                    update.old_version,
+                    update.new_version
+                );
+                SelectItem((addon_type, idx, update), Cow::Owned(label))
+            })
+            .collect::<Vec<_>>();
+
+        app.multi_select("Which addons should be updated?", &items)?
+    };
+
+    if selected.is_empty() {
+        app.info("Nothing was updated");
+        return Ok(());
+    }
+
+    let mut unpinned = 0;
+    for (addon_type, idx, update) in &selected {
+        let addon = match addon_type {
+            AddonType::Plugin => &mut app.server.plugins[*idx],
+            AddonType::Mod => &mut app.server.mods[*idx],
+        };
+
+        if addon.options().hash.is_some() {
+            unpinned += 1;
+        }
+
+        *addon = update.addon.clone();
+    }
+
+    app.save_changes()?;
+    app.success(format!(
+        "Updated {} addon{}",
+        selected.len(),
+        if selected.len() == 1 { "" } else { "s" }
+    ));
+    if unpinned != 0 {
+        app.info(format!(
+            "The hash of {unpinned} updated addon{} was removed, run `mcman hash` to pin the new version",
+            if unpinned == 1 { "" } else { "s" }
+        ));
+    }
+    app.refresh_markdown().await?;
+
+    Ok(())
+}
+
+/// Updates for the addons in server.toml as (index, update). Addons that fail
+/// to check or whose new version doesn't resolve are skipped with a warning
+async fn find_updates(app: &App, addon_type: AddonType) -> Vec<(usize, AddonUpdate)> {
+    let list: &[Downloadable] = match addon_type {
+        AddonType::Plugin => &app.server.plugins,
+        AddonType::Mod => &app.server.mods,
+    };
+
+    stream::iter(list.iter().enumerate().map(|(idx, addon)| async move {
+        let update = match addon.check_update(app).await {
+            Ok(update) => update?,
+            Err(e) => {
+                app.warn(format!(
+                    "Couldn't check {} for updates: {e:#}",
+                    addon.to_short_string()
+                ));
+                return None;
+            }
+        };
+
+        if let Err(e) = update.addon.resolve_source(app).await {
+            app.warn(format!(
+                "{} {} doesn't resolve, skipping: {e:#}",
+                addon.to_short_string(),
+                update.new_version
+            ));
+            return None;
+        }
+
+        Some((idx, update))
+    }))
+    .buffered(app.parallel_downloads())
+    .filter_map(|update| async move { update })
+    .collect()
+    .await
+}
+
+fn print_changelog(app: &App, changelog: &str) {
+    let lines = changelog
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+
+    for line in lines.iter().take(CHANGELOG_LINES) {
+        app.log(format!("    {}", style(line).dim()));
+    }
+
+    if lines.len() > CHANGELOG_LINES {
+        app.log(format!(
+            "    {}",
+            style(format!("... {} more lines", lines.len() - CHANGELOG_LINES)).dim()
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use console::style;
+
+use crate::{
+    app::{AddonType, App, ResolvedFile},
+    model::Lockfile,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// The output directory for the server
+    #[arg(short, long, value_name = "file")]
+    output: Option<PathBuf>,
+}
+
+/// A file the lockfile knows about
+struct Expected {
+    /// Relative to the output directory
+    path: PathBuf,
+    /// (algorithm, hash)
+    hash: Option<(String, String)>,
+}
+
+impl Expected {
+    fn resolved(folder: &str, resolved: &ResolvedFile) -> Self {
+        Self {
+            path: Path::new(folder).join(&resolved.filename),
+            hash: resolved
+                .hashes
+                .get_key_value("sha256")
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .or_else(|| App::get_best_hash(&resolved.hashes)),
+        }
+    }
+}
+
+pub async fn run(app: &App, args: Args) -> Result<()> {
+    let output_dir = args.output.unwrap_or_else(|| app.server.output_dir());
+
+    if !output_dir.join(".mcman.lock").exists() {
+        bail!(
+            "There's no lockfile in '{}', build the server first",
+            output_dir.display()
+        );
+    }
+
+    let lockfile = Lockfile::get_lockfile(&output_dir).context("Reading the lockfile")?;
+
+    app.print_job(&format!(
+        "{} {}...",
+        style("Verifying").bold(),
+        style(output_dir.display()).dim()
+    ));
+
+    let expected = expected_files(&lockfile);
+
+    let (mut missing, mut modified, mut unverified) = (0, 0, 0);
+
+    for file in &expected {
+        let path = output_dir.join(&file.path);
+
+        if !path.is_file() {
+            app.log(format!(
+                "{} {}",
+                style("missing  ").red().bold(),
+                file.path.display()
+            ));
+            missing += 1;
+            continue;
+        }
+
+        let Some((algo, hash)) = &file.hash else {
+            app.dbg(format!(
+                "{} has no hash in the lockfile",
+                file.path.display()
+            ));
+            unverified += 1;
+            continue;
+        };
+
+        if &App::hash_file(&path, algo).await? != hash {
+            app.log(format!(
+                "{} {}",
+                style("modified ").yellow().bold(),
+                file.path.display()
+            ));
+            modified += 1;
+        }
+    }
+
+    let known = expected
+        .iter()
+        .map(|file| file.path.clone())
+        .collect::<HashSet<_>>();
+    let mut unmanaged = 0;
+
+    let mut folders = vec![
+        AddonType::Plugin.folder().to_owned(),
+        AddonType::Mod.folder().to_owned(),
+    ];
+    folders.extend(
+        app.server
+            .worlds
+            .keys()
+            .chain(lockfile.worlds.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|world| format!("{world}/datapacks")),
+    );
+
+    for folder in &folders {
+        for path in unmanaged_files(&output_dir, folder, &known)? {
+            app.log(format!(
+                "{} {}",
+                style("unmanaged").cyan().bold(),
+                path.display()
+            ));
+            unmanaged += 1;
+        }
+    }
+
+    if unverified != 0 {
+        app.warn(format!(
+            "{unverified} file{} had no hash in the lockfile, rebuild to add them",
+            if unverified == 1 { "" } else { "s" }
+        ));
+    }
+
+    let problems = missing + modified + unmanaged;
+    if problems != 0 {
+        bail!("Output doesn't match the lockfile: {missing} missing, {modified} modified, {unmanaged} unmanaged");
+    }
+
+    app.success(format!("All {} files match the lockfile", expected.len()));
+
+    Ok(())
+}
+
+/// Every file in the output the lockfile knows about
+fn expected_files(lockfile: &Lockfile) -> Vec<Expected> {
+    let mut expected = vec![];
+
+    if let Some(server_jar) = &lockfile.server_jar {
+        expected.push(match &server_jar.installed {
+            Some((path, hash)) => Expected {
+                path: path.clone(),
+                hash: Some(("sha256".to_owned(), hash.clone())),
+            },
+            None => Expected::resolved("", &server_jar.resolved),
+        });
+    }
+
+    for (addon_type, list) in [
+        (AddonType::Plugin, &lockfile.plugins),
+        (AddonType::Mod, &lockfile.mods),
+    ] {
+        for (_, resolved) in list {
+            expected.push(Expected::resolved(addon_type.folder(), resolved));
+        }
+    }
+
+    for (world, locked) in &lockfile.worlds {
+        let folder = format!("{world}/datapacks");
+        for (_, resolved) in &locked.datapacks {
+            expected.push(Expected::resolved(&folder, resolved));
+        }
+    }
+
+    for file in &lockfile.files {
+        expected.push(Expected {
+            path: file.path.clone(),
+            hash: file.hash.clone().map(|hash| ("sha256".to_owned(), hash)),
+        });
+    }
+
+    expected
+}
+
+/// Files directly in `folder` that the lockfile doesn't know about
+fn unmanaged_files(
+    output_dir: &Path,
+    folder: &str,
+    known: &HashSet<PathBuf>,
+) -> Result<Vec<PathBuf>> {
+    let dir = output_dir.join(folder);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut list = vec![];
+    for entry in std::fs::read_dir(&dir).context(format!("Reading {}", dir.display()))? {
+        let entry = entry?;
+        let path = Path::new(folder).join(entry.file_name());
+
+        if entry.file_type()?.is_file() && !known.contains(&path) {
+            list.push(path);
+        }
+    }
+
+    list.sort();
+    Ok(list)
+}
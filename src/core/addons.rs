@@ -1,16 +1,36 @@
-use std::{collections::HashSet, io::ErrorKind, time::Duration};
+use std::{
+    collections::HashSet,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use anyhow::Result;
-use indicatif::{FormattedDuration, ProgressBar, ProgressIterator, ProgressStyle};
+use anyhow::{anyhow, bail, Context, Result};
+use futures_util::{stream, StreamExt, TryStreamExt};
+use indicatif::{FormattedDuration, ProgressBar, ProgressStyle};
 use tokio::fs;
 
-use crate::app::AddonType;
+use crate::{
+    app::{AddonType, FileOrigin, ResolvedFile},
+    model::Downloadable,
+};
 
-use super::BuildContext;
+use super::{report::ReportAddon, BuildContext};
 
 impl<'a> BuildContext<'a> {
+    #[allow(clippy::too_many_lines)]
     pub async fn download_addons(&mut self, addon_type: AddonType) -> Result<()> {
-        let server_list = self.app.get_addons(addon_type);
+        let mut server_list = Downloadable::expand_files(
+            self.app.active_addons(self.app.get_addons(addon_type))?,
+            &self.app.server.path,
+        )?;
+        if self.locked {
+            let dependencies = self.locked_dependencies(addon_type);
+            self.new_lockfile
+                .dependencies
+                .extend(dependencies.iter().cloned());
+            server_list.extend(dependencies);
+        }
         let addons = match addon_type {
             AddonType::Plugin => &self.lockfile.plugins,
             AddonType::Mod => &self.lockfile.mods,
@@ -51,18 +71,50 @@ impl<'a> BuildContext<'a> {
 
         let pb = self.app.multi_progress.add(pb);
 
-        for addon in server_list.iter().progress_with(pb.clone()) {
-            let (_path, resolved) = self
-                .downloadable(addon, addon_type.folder(), Some(&pb))
+        let locked_list = if self.locked {
+            Some(
+                self.locked_addons(addon_type, &server_list)
+                    .context("server.toml and the lockfile disagree")?,
+            )
+        } else {
+            None
+        };
+
+        let mut resolved_list = self
+            .download_list(addon_type, &server_list, locked_list.as_ref(), &pb)
+            .await?;
+
+        if !self.locked && matches!(addon_type, AddonType::Plugin) {
+            self.check_hangar_dependencies(&resolved_list).await;
+        }
+
+        if !self.locked {
+            let dependencies = self
+                .implicit_dependencies(addon_type, &server_list, &resolved_list)
                 .await?;
 
+            if !dependencies.is_empty() {
+                pb.inc_length(dependencies.len() as u64);
+                resolved_list.extend(
+                    self.download_list(addon_type, &dependencies, None, &pb)
+                        .await?,
+                );
+                self.new_lockfile.dependencies.extend(dependencies);
+            }
+        }
+
+        for (addon, _path, resolved, origin) in resolved_list {
             files_list.insert(resolved.filename.clone());
 
+            self.report
+                .addons
+                .push(ReportAddon::new(addon_type, &addon, &resolved, origin));
+
             match addon_type {
                 AddonType::Plugin => &mut self.new_lockfile.plugins,
                 AddonType::Mod => &mut self.new_lockfile.mods,
             }
-            .push((addon.clone(), resolved));
+            .push((addon, resolved));
         }
 
         pb.set_style(ProgressStyle::with_template(
@@ -101,4 +153,105 @@ impl<'a> BuildContext<'a> {
 
         Ok(())
     }
+
+    /// Resolve and download `list` concurrently, `buffered` keeps the results
+    /// in the same order so the lockfile stays deterministic
+    async fn download_list(
+        &self,
+        addon_type: AddonType,
+        list: &[Downloadable],
+        locked_list: Option<&Vec<ResolvedFile>>,
+        parent: &ProgressBar,
+    ) -> Result<Vec<(Downloadable, PathBuf, ResolvedFile, FileOrigin)>> {
+        stream::iter(list.iter().enumerate().map(|(idx, addon)| async move {
+            let (path, resolved, origin) = if let Some(locked) = locked_list {
+                self.download_resolved(&locked[idx], addon_type.folder(), Some(parent))
+                    .await?
+            } else {
+                let downloaded = self
+                    .downloadable(addon, addon_type.folder(), Some(parent))
+                    .await?;
+                self.check_first_use(addon_type, &downloaded.0, &downloaded.1)?;
+                downloaded
+            };
+
+            Ok::<_, anyhow::Error>((addon.clone(), path, resolved, origin))
+        }))
+        .buffered(self.app.parallel_downloads())
+        .inspect(|_| parent.inc(1))
+        .try_collect()
+        .await
+    }
+
+    /// With `trust_on_first_use`, fail if `resolved` is in the lockfile but
+    /// the file changed since, deleting it so it isn't used
+    fn check_first_use(
+        &self,
+        addon_type: AddonType,
+        path: &Path,
+        resolved: &ResolvedFile,
+    ) -> Result<()> {
+        if !self.app.server.options.trust_on_first_use {
+            return Ok(());
+        }
+
+        let Some(first) = match addon_type {
+            AddonType::Plugin => &self.lockfile.plugins,
+            AddonType::Mod => &self.lockfile.mods,
+        }
+        .iter()
+        .find(|(_, res)| res.url == resolved.url && res.filename == resolved.filename)
+        .and_then(|(_, res)| res.hashes.get("sha256")) else {
+            return Ok(());
+        };
+
+        match resolved.hashes.get("sha256") {
+            Some(hash) if hash != first => {
+                let _ = std::fs::remove_file(path);
+
+                bail!(
+                    "{} ({}) changed since it was first downloaded!
+    sha256 in the lockfile: {first}
+    sha256 now: {hash}
+If this is expected, remove it from '{}'",
+                    resolved.filename,
+                    resolved.url,
+                    self.lockfile.path.display()
+                );
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The locked `ResolvedFile` for every addon in `list`, in the same order
+    pub fn locked_addons(
+        &self,
+        addon_type: AddonType,
+        list: &[Downloadable],
+    ) -> Result<Vec<ResolvedFile>> {
+        let locked = match addon_type {
+            AddonType::Plugin => &self.lockfile.plugins,
+            AddonType::Mod => &self.lockfile.mods,
+        };
+
+        if let Some((dl, _)) = locked.iter().find(|(dl, _)| !list.contains(dl)) {
+            bail!(
+                "{addon_type} {} is in the lockfile but not in server.toml",
+                dl.to_short_string()
+            );
+        }
+
+        list.iter()
+            .map(|addon| {
+                locked
+                    .iter()
+                    .find(|(dl, _)| dl == addon)
+                    .map(|(_, resolved)| resolved.clone())
+                    .ok_or(anyhow!(
+                        "{addon_type} {} is not in the lockfile",
+                        addon.to_short_string()
+                    ))
+            })
+            .collect()
+    }
 }
@@ -1,4 +1,6 @@
 use std::{
+    collections::{HashMap, HashSet},
+    io::ErrorKind,
     path::{Path, PathBuf},
     time::{Duration, SystemTime},
 };
@@ -9,9 +11,12 @@ use pathdiff::diff_paths;
 use tokio::fs;
 use walkdir::WalkDir;
 
-use crate::model::BootstrappedFile;
+use crate::{app::App, model::BootstrappedFile, util};
 
-use super::BuildContext;
+use super::{
+    report::{FileStatus, ReportFile},
+    BuildContext,
+};
 
 impl<'a> BuildContext<'a> {
     pub async fn bootstrap_files(&mut self) -> Result<()> {
@@ -28,55 +33,124 @@ impl<'a> BuildContext<'a> {
         );
         pb.enable_steady_tick(Duration::from_millis(250));
 
-        /* let lockfile_entries = self
-        .lockfile
-        .files
-        .iter()
-        .map(|e| (e.path.clone(), e.date))
-        .collect::<HashMap<_, _>>(); */
+        let lockfile_entries = self
+            .lockfile
+            .files
+            .iter()
+            .map(|e| (e.path.clone(), e.clone()))
+            .collect::<HashMap<_, _>>();
 
-        // wtf i have to clone it sorry null
-        if let Some(nw) = self.app.network.clone() {
-            self.bootstrap_folder(nw.path.join("groups").join("global").join("config"))
-                .await?;
+        let (mut written, mut skipped) = (0, 0);
 
-            if self.app.server.name == nw.proxy {
-                for group_name in &nw.proxy_groups {
-                    self.bootstrap_folder(nw.path.join("groups").join(group_name).join("config"))
-                        .await?;
-                }
+        let sources = self.bootstrap_sources()?;
+
+        for (source, rel_path) in &sources {
+            pb.set_message(rel_path.display().to_string());
+
+            let changed = self
+                .bootstrap_file(source, rel_path, lockfile_entries.get(rel_path))
+                .await
+                .context(format!(
+                    "Bootstrapping file:
+                - Entry: {}
+                - Relative: {}",
+                    source.display(),
+                    rel_path.display()
+                ))?;
+
+            if changed {
+                written += 1;
+            } else {
+                skipped += 1;
             }
 
-            if let Some(entry) = nw.servers.get(&self.app.server.name) {
-                for group_name in &entry.groups {
-                    self.bootstrap_folder(nw.path.join("groups").join(group_name).join("config"))
-                        .await?;
+            self.report.files.push(ReportFile {
+                path: rel_path.clone(),
+                status: if changed {
+                    FileStatus::Written
+                } else {
+                    FileStatus::Unchanged
+                },
+            });
+        }
+
+        let mut removed = 0;
+        if !self.app.server.options.keep_stale_files {
+            pb.set_prefix("Deleting");
+
+            for rel_path in self.stale_files(&sources) {
+                pb.set_message(rel_path.display().to_string());
+
+                match fs::remove_file(self.output_dir.join(&rel_path)).await {
+                    Err(err) if err.kind() == ErrorKind::NotFound => {
+                        self.app.dbg(format!(
+                            "stale file {} was already deleted",
+                            rel_path.display()
+                        ));
+                        Ok(())
+                    }
+                    o => o,
                 }
+                .context(format!("Deleting stale file {}", rel_path.display()))?;
+
+                self.app.log_dev(format!("-  {}", rel_path.display()));
+                removed += 1;
+
+                self.report.files.push(ReportFile {
+                    path: rel_path,
+                    status: FileStatus::Removed,
+                });
             }
         }
 
-        self.bootstrap_folder(self.app.server.path.join("config"))
-            .await?;
-
         pb.disable_steady_tick();
         pb.finish_and_clear();
-        self.app.success("Bootstrapping complete");
+        self.app.success(format!(
+            "Bootstrapping complete, {written} file{} written, {skipped} unchanged{}",
+            if written == 1 { "" } else { "s" },
+            if removed == 0 {
+                String::new()
+            } else {
+                format!(", {removed} removed")
+            },
+        ));
 
         self.app.ci("::endgroup::");
 
         Ok(())
     }
 
-    pub async fn bootstrap_folder(&mut self, from_path: PathBuf) -> Result<()> {
-        if !from_path.exists() {
-            self.app.dbg(format!(
-                "skipped bootstrapping {} because it doesnt exist",
-                from_path.display()
-            ));
-            return Ok(());
+    /// Folders to bootstrap from, in order. Network group folders come first
+    /// so the server's own `config/` can override them
+    pub fn bootstrap_folders(&self) -> Vec<PathBuf> {
+        let mut folders = vec![];
+
+        if let Some(nw) = &self.app.network {
+            folders.push(nw.path.join("groups").join("global").join("config"));
+
+            if self.app.server.name == nw.proxy {
+                for group_name in &nw.proxy_groups {
+                    folders.push(nw.path.join("groups").join(group_name).join("config"));
+                }
+            }
+
+            if let Some(entry) = nw.servers.get(&self.app.server.name) {
+                for group_name in &entry.groups {
+                    folders.push(nw.path.join("groups").join(group_name).join("config"));
+                }
+            }
         }
 
-        for entry in WalkDir::new(&from_path) {
+        folders.push(self.app.server.path.join("config"));
+
+        folders
+    }
+
+    /// Every file under `from_path` as (full path, path relative to `from_path`)
+    pub fn walk_bootstrap_folder(from_path: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let mut files = vec![];
+
+        for entry in WalkDir::new(from_path) {
             let entry = entry.map_err(|e| {
                 anyhow!(
                     "Can't walk directory/file: {}",
@@ -89,27 +163,53 @@ impl<'a> BuildContext<'a> {
             }
 
             let source = entry.path();
-            let diffed_paths =
-                diff_paths(source, &from_path).ok_or(anyhow!("Cannot diff paths"))?;
-
-            //pb.set_message(diffed_paths.to_string_lossy().to_string());
-
-            self.bootstrap_file(
-                &source.to_path_buf(),
-                &diffed_paths,
-                None, /* lockfile_entries.get(&diffed_paths) */
-            )
-            .await
-            .context(format!(
-                "Bootstrapping file:
-                - Entry: {}
-                - Relative: {}",
-                entry.path().display(),
-                diffed_paths.display()
-            ))?;
+            let diffed_paths = diff_paths(source, from_path).ok_or(anyhow!("Cannot diff paths"))?;
+
+            files.push((source.to_path_buf(), diffed_paths));
         }
 
-        Ok(())
+        Ok(files)
+    }
+
+    /// Every file to bootstrap as (full path, relative path), in order.
+    /// When folders have the same file, only the last one is kept
+    pub fn bootstrap_sources(&self) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let mut sources: Vec<(PathBuf, PathBuf)> = vec![];
+        // index into `sources` by relative path
+        let mut indexes: HashMap<PathBuf, usize> = HashMap::new();
+
+        for folder in self.bootstrap_folders() {
+            if !folder.exists() {
+                self.app.dbg(format!(
+                    "skipped bootstrapping {} because it doesnt exist",
+                    folder.display()
+                ));
+                continue;
+            }
+
+            for (source, rel_path) in Self::walk_bootstrap_folder(&folder)? {
+                if let Some(&idx) = indexes.get(&rel_path) {
+                    sources[idx].0 = source;
+                } else {
+                    indexes.insert(rel_path.clone(), sources.len());
+                    sources.push((source, rel_path));
+                }
+            }
+        }
+
+        Ok(sources)
+    }
+
+    /// Files bootstrapped by the previous build that no longer have a source
+    pub fn stale_files(&self, sources: &[(PathBuf, PathBuf)]) -> Vec<PathBuf> {
+        let rel_paths: HashSet<&PathBuf> = sources.iter().map(|(_, rel_path)| rel_path).collect();
+
+        self.lockfile
+            .files
+            .iter()
+            .filter(|file| !rel_paths.contains(&file.path))
+            .map(|file| file.path.clone())
+            .collect()
     }
 
     pub fn should_bootstrap_file(&self, path: &Path) -> bool {
@@ -142,12 +242,36 @@ impl<'a> BuildContext<'a> {
                 .any(|s| s.as_str() == ext)
     }
 
+    /// Whether the file at `rel_path` in the output is still what the lockfile
+    /// entry says. Templated files are compared by the hash of their rendered
+    /// contents, so variable changes are picked up too
+    pub fn is_bootstrapped(
+        &self,
+        rel_path: &Path,
+        modified: Option<SystemTime>,
+        rendered_hash: Option<&str>,
+        cache: Option<&BootstrappedFile>,
+    ) -> bool {
+        let Some(cache) = cache else {
+            return false;
+        };
+
+        !self.force
+            && self.output_dir.join(rel_path).exists()
+            && match (rendered_hash, &cache.hash) {
+                (Some(new), Some(old)) => new == old,
+                (Some(_), None) => false,
+                (None, _) => modified.is_some_and(|time| time <= cache.date),
+            }
+    }
+
+    /// Bootstrap a single file, returns `false` if it was skipped because it didn't change
     pub async fn bootstrap_file(
         &mut self,
         full_path: &PathBuf,
         rel_path: &PathBuf,
-        cache: Option<&SystemTime>,
-    ) -> Result<()> {
+        cache: Option<&BootstrappedFile>,
+    ) -> Result<bool> {
         let pretty_path = rel_path.display();
 
         let source = full_path;
@@ -164,40 +288,37 @@ impl<'a> BuildContext<'a> {
 
         let modified = metadata.modified();
 
-        if self.force || {
-            if let Some(time) = cache {
-                if let Ok(source_time) = modified {
-                    &source_time > time
-                } else {
-                    true
-                }
-            } else {
-                true
-            }
-        } {
+        let bootstrapped_contents = if self.should_bootstrap_file(rel_path) {
+            let config_contents = fs::read_to_string(&source).await.context(format!(
+                "Reading from '{}' ; [{pretty_path}]",
+                source.display()
+            ))?;
+
+            Some(self.bootstrap_content(&config_contents))
+        } else {
+            None
+        };
+
+        let hash = bootstrapped_contents.as_deref().map(App::hash_sha256);
+
+        let changed = !self.is_bootstrapped(
+            rel_path,
+            modified.as_ref().ok().copied(),
+            hash.as_deref(),
+            cache,
+        );
+
+        if changed {
             fs::create_dir_all(dest.parent().unwrap())
                 .await
                 .context("Creating parent directory")?;
+            util::unlink(&dest).context(format!("Removing '{}'", dest.display()))?;
 
-            if self.should_bootstrap_file(rel_path) {
-                let config_contents = fs::read_to_string(&source).await.context(format!(
-                    "Reading from '{}' ; [{pretty_path}]",
-                    source.display()
-                ))?;
-                let permissions = fs::metadata(&source)
-                    .await
-                    .context(format!(
-                        "Getting metadata of '{}' ; [{pretty_path}]",
-                        source.display()
-                    ))?
-                    .permissions();
-
-                let bootstrapped_contents = self.bootstrap_content(&config_contents);
-
+            if let Some(bootstrapped_contents) = bootstrapped_contents {
                 fs::write(&dest, bootstrapped_contents)
                     .await
                     .context(format!("Writing to '{}' ; [{pretty_path}]", dest.display()))?;
-                fs::set_permissions(&dest, permissions)
+                fs::set_permissions(&dest, metadata.permissions())
                     .await
                     .context(format!(
                         "Setting permissions for '{}' ; [{pretty_path}]",
@@ -217,16 +338,24 @@ impl<'a> BuildContext<'a> {
             self.app.log_dev(format!("   {pretty_path}"));
         }
 
+        // copied files are hashed too, so `mcman verify` can check them
+        let hash = match (hash, cache.and_then(|c| c.hash.clone())) {
+            (Some(hash), _) => hash,
+            (None, Some(hash)) if !changed => hash,
+            (None, _) => App::hash_file(&dest, "sha256").await?,
+        };
+
         if let Ok(source_time) = modified {
             self.new_lockfile.files.push(BootstrappedFile {
                 path: rel_path.clone(),
                 date: source_time,
+                hash: Some(hash),
             });
         } else {
             self.app.warn("File metadata not supported");
         }
 
-        Ok(())
+        Ok(changed)
     }
 
     pub fn bootstrap_content(&self, content: &str) -> String {
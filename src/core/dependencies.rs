@@ -0,0 +1,140 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use anyhow::Result;
+
+use crate::{
+    app::{AddonType, FileOrigin, ResolvedFile},
+    model::{DownloadOptions, Downloadable},
+};
+
+use super::BuildContext;
+
+impl<'a> BuildContext<'a> {
+    /// Addons of `addon_type` that were added as dependencies in the lockfile
+    pub fn locked_dependencies(&self, addon_type: AddonType) -> Vec<Downloadable> {
+        match addon_type {
+            AddonType::Plugin => &self.lockfile.plugins,
+            AddonType::Mod => &self.lockfile.mods,
+        }
+        .iter()
+        .map(|(dl, _)| dl)
+        .filter(|dl| self.lockfile.dependencies.contains(dl))
+        .cloned()
+        .collect()
+    }
+
+    /// Required Modrinth dependencies of the downloaded addons that aren't in server.toml,
+    /// with `resolve_dependencies`. Files are looked up by their hash, so addons from
+    /// other sources count too
+    pub async fn implicit_dependencies(
+        &self,
+        addon_type: AddonType,
+        server_list: &[Downloadable],
+        resolved_list: &[(Downloadable, PathBuf, ResolvedFile, FileOrigin)],
+    ) -> Result<Vec<Downloadable>> {
+        if !self.app.server.options.resolve_dependencies || resolved_list.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let api = self.app.modrinth();
+        let files = resolved_list
+            .iter()
+            .map(|(_, path, _, _)| path.clone())
+            .collect::<Vec<_>>();
+
+        // modrinth being unreachable shouldn't break builds that might not need it
+        let versions = match api.versions_of_files(&files).await {
+            Ok(versions) => versions,
+            Err(err) => {
+                self.app.warn(format!(
+                    "Couldn't look up {addon_type}s on Modrinth, skipping dependencies: {err:#}"
+                ));
+                return Ok(vec![]);
+            }
+        };
+
+        let roots = resolved_list
+            .iter()
+            .zip(versions)
+            .filter_map(|((addon, ..), version)| Some((addon.to_short_string(), version?)))
+            .collect::<Vec<_>>();
+
+        let installed = server_list
+            .iter()
+            .filter_map(|addon| match addon {
+                Downloadable::Modrinth { id, .. } => Some(id.clone()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+
+        let result = match api.resolve_dependencies(&roots, &installed).await {
+            Ok(result) => result,
+            Err(err) => {
+                self.app.warn(format!(
+                    "Couldn't resolve Modrinth dependencies of {addon_type}s, skipping them: {err:#}"
+                ));
+                return Ok(vec![]);
+            }
+        };
+
+        for (addon, incompatible) in &result.conflicts {
+            self.app
+                .warn(format!("{addon} is incompatible with {incompatible}"));
+        }
+
+        Ok(result
+            .missing
+            .into_iter()
+            .map(|dep| {
+                self.app.info(format!(
+                    "Adding {addon_type} {} ({}), required by {}",
+                    dep.project.title, dep.version.version_number, dep.required_by
+                ));
+
+                Downloadable::Modrinth {
+                    id: dep.project.slug,
+                    version: dep.version.id,
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
+                }
+            })
+            .collect())
+    }
+
+    /// Warn about required plugins of Hangar plugins that aren't installed
+    pub async fn check_hangar_dependencies(
+        &self,
+        resolved_list: &[(Downloadable, PathBuf, ResolvedFile, FileOrigin)],
+    ) {
+        let plugins = resolved_list
+            .iter()
+            .map(|(addon, _, resolved, _)| (addon.clone(), resolved.clone()))
+            .collect::<Vec<_>>();
+
+        for (addon, _, resolved, _) in resolved_list {
+            let (Downloadable::Hangar { id, .. }, Some(version)) = (addon, &resolved.version)
+            else {
+                continue;
+            };
+
+            match self
+                .app
+                .hangar()
+                .missing_dependencies(id, version, &plugins)
+                .await
+            {
+                Ok(missing) => {
+                    for dependency in missing {
+                        self.app.warn(format!(
+                            "Hangar project '{id}' requires the plugin {dependency}, which isn't in plugins"
+                        ));
+                    }
+                }
+                Err(err) => self.app.warn(format!(
+                    "Couldn't check the dependencies of Hangar project '{id}': {err:#}"
+                )),
+            }
+        }
+    }
+}
@@ -1,22 +1,37 @@
-use std::{collections::HashMap, fmt::Debug, path::PathBuf, process::Child, time::Duration};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::PathBuf,
+    process::Child,
+    time::{Duration, Instant},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use console::style;
 use indicatif::{FormattedDuration, ProgressBar};
 use tokio::{fs::File, io::AsyncWriteExt};
 
 use crate::{
-    app::{AddonType, App, Resolvable, ResolvedFile},
+    app::{App, DownloadTask, FileOrigin, KnownFile, Resolvable, ResolvedFile, Step},
     model::{HookEvent, Lockfile},
+    util,
 };
 
+use self::report::BuildReport;
+
 pub mod addons;
 pub mod bootstrap;
+pub mod dependencies;
+pub mod plan;
+pub mod report;
 pub mod scripts;
 pub mod serverjar;
+pub mod stages;
+pub mod staging;
 pub mod worlds;
 
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct BuildContext<'a> {
     pub app: &'a mut App,
 
@@ -25,12 +40,39 @@ pub struct BuildContext<'a> {
     pub new_lockfile: Lockfile,
 
     pub force: bool,
+    pub locked: bool,
+    pub atomic: bool,
+    /// The real output directory while `output_dir` points to the staging directory
+    pub live_output_dir: Option<PathBuf>,
+    pub report: BuildReport,
+    pub report_path: Option<PathBuf>,
+    pub github_summary: bool,
     pub skip_stages: Vec<String>,
     pub server_process: Option<Child>,
 }
 
 impl<'a> BuildContext<'a> {
     pub async fn build_all(&mut self) -> Result<String> {
+        let started = Instant::now();
+        self.report = BuildReport {
+            server: self.app.server.name.clone(),
+            ..Default::default()
+        };
+
+        let result = self.build().await;
+
+        self.report.success = result.is_ok();
+        self.report.error = result.as_ref().err().map(|e| format!("{e:?}"));
+        self.report.seconds = started.elapsed().as_secs_f64();
+        if let Err(e) = self.write_report() {
+            self.app
+                .error(format!("Couldn't write the build report: {e:#}"));
+        }
+
+        result
+    }
+
+    async fn build(&mut self) -> Result<String> {
         let server_name = self.app.server.name.clone();
         let banner = format!(
             "{} {}...",
@@ -45,11 +87,17 @@ impl<'a> BuildContext<'a> {
             .with_message(banner);
         progress_bar.enable_steady_tick(Duration::from_millis(250));
 
+        if self.atomic {
+            self.begin_staging().await?;
+        }
+
         tokio::fs::create_dir_all(&self.output_dir)
             .await
             .context("Creating output directory")?;
 
-        self.reload();
+        self.reload()?;
+
+        let stages = self.stages()?;
 
         if !self.skip_stages.is_empty() {
             self.app
@@ -57,46 +105,16 @@ impl<'a> BuildContext<'a> {
         }
 
         // hook: PreBuild
+        let data = self.hook_data();
         self.app
             .hooks()
-            .event(HookEvent::PreBuild, HashMap::default())
+            .event(HookEvent::PreBuild, data, &mut self.report.hooks)
             .await?;
 
-        // actual stages contained here
-
-        self.app.ci("::group::Server Jar");
-        let server_jar = self.download_server_jar().await?;
-        self.app.ci("::endgroup::");
-
-        if self.skip_stages.iter().all(|s| s.as_str() != "plugins") {
-            self.download_addons(AddonType::Plugin).await?;
-        }
-
-        if self.skip_stages.iter().all(|s| s.as_str() != "mods") {
-            self.download_addons(AddonType::Mod).await?;
-        }
-
-        if !self.app.server.worlds.is_empty()
-            && self.skip_stages.iter().all(|s| s.as_str() != "worlds")
-        {
-            self.process_worlds().await?;
-        }
-
-        if self.app.server.path.join("config").exists()
-            && self.skip_stages.iter().all(|s| s.as_str() != "bootstrap")
-        {
-            self.bootstrap_files().await?;
-        }
-
-        if !self.app.server.launcher.disable {
-            let startup = self.get_startup_method(&server_jar).await?;
-
-            self.create_scripts(startup).await?;
-
-            self.app.log("start.bat and start.sh created");
-        }
+        let server_jar = self.run_stages(&stages).await?;
 
         if self.app.server.launcher.eula_args && !self.app.server.jar.supports_eula_args() {
+            util::unlink(&self.output_dir.join("eula.txt"))?;
             File::create(self.output_dir.join("eula.txt"))
                 .await?
                 .write_all(b"eula=true\n")
@@ -107,11 +125,14 @@ impl<'a> BuildContext<'a> {
         self.write_lockfile()?;
 
         // hook: PostBuild
+        let data = self.hook_data();
         self.app
             .hooks()
-            .event(HookEvent::PostBuild, HashMap::default())
+            .event(HookEvent::PostBuild, data, &mut self.report.hooks)
             .await?;
 
+        self.finish_staging().await?;
+
         progress_bar.disable_steady_tick();
         progress_bar.finish_and_clear();
 
@@ -124,15 +145,28 @@ impl<'a> BuildContext<'a> {
         Ok(server_jar)
     }
 
+    fn hook_data(&self) -> HashMap<String, String> {
+        HashMap::from([(
+            "OUTPUT_DIR".to_owned(),
+            self.output_dir.to_string_lossy().into_owned(),
+        )])
+    }
+
     /// Load to `self.lockfile` and create a default one at `self.new_lockfile`
-    pub fn reload(&mut self) {
-        self.lockfile = if let Ok(f) = Lockfile::get_lockfile(&self.output_dir) {
-            f
-        } else {
-            self.app.warn("Lockfile error, using default");
-            Lockfile {
-                path: self.output_dir.join(".mcman.lock"),
-                ..Default::default()
+    pub fn reload(&mut self) -> Result<()> {
+        if self.locked && !self.output_dir.join(".mcman.lock").exists() {
+            bail!("--locked needs a lockfile, build without --locked first to create one");
+        }
+
+        self.lockfile = match Lockfile::get_lockfile(&self.output_dir) {
+            Ok(f) => f,
+            Err(e) if self.locked => return Err(e.context("Reading lockfile")),
+            Err(_) => {
+                self.app.warn("Lockfile error, using default");
+                Lockfile {
+                    path: self.output_dir.join(".mcman.lock"),
+                    ..Default::default()
+                }
             }
         };
 
@@ -140,6 +174,8 @@ impl<'a> BuildContext<'a> {
             path: self.output_dir.join(".mcman.lock"),
             ..Default::default()
         };
+
+        Ok(())
     }
 
     /// Save `new_lockfile`
@@ -162,23 +198,89 @@ impl<'a> BuildContext<'a> {
         resolvable: &(impl Resolvable + Debug + ToString),
         folder_path: &str,
         parent_progress: Option<&ProgressBar>,
-    ) -> Result<(PathBuf, ResolvedFile)> {
-        let progress_bar = if let Some(parent) = parent_progress {
+    ) -> Result<(PathBuf, ResolvedFile, FileOrigin)> {
+        let progress_bar = self.progress_bar(parent_progress);
+
+        let resolved = self.app.resolve(resolvable, &progress_bar).await?;
+
+        self.download_step(resolved, folder_path, progress_bar)
+            .await
+    }
+
+    /// Like [`Self::downloadable`] but for an already resolved file, e.g. from the lockfile
+    pub async fn download_resolved(
+        &self,
+        resolved: &ResolvedFile,
+        folder_path: &str,
+        parent_progress: Option<&ProgressBar>,
+    ) -> Result<(PathBuf, ResolvedFile, FileOrigin)> {
+        let progress_bar = self.progress_bar(parent_progress);
+        progress_bar.set_message(resolved.filename.clone());
+
+        self.download_step(resolved.clone(), folder_path, progress_bar)
+            .await
+    }
+
+    async fn download_step(
+        &self,
+        resolved: ResolvedFile,
+        folder_path: &str,
+        progress_bar: ProgressBar,
+    ) -> Result<(PathBuf, ResolvedFile, FileOrigin)> {
+        let path = self.output_dir.join(folder_path);
+        let file_path = path.join(&resolved.filename);
+
+        let step = Step::Download(DownloadTask {
+            known: self.locked_file(&resolved),
+            resolved_file: resolved.clone(),
+            path,
+        });
+        let (resolved, origin) = self
+            .app
+            .run_step(&step, progress_bar)
+            .await?
+            .unwrap_or((resolved, FileOrigin::Downloaded));
+
+        Ok((file_path, resolved, origin))
+    }
+
+    /// The sha256 and size the lockfile has for the same file, from when it was downloaded
+    fn locked_file(&self, resolved: &ResolvedFile) -> Option<KnownFile> {
+        let lockfile = &self.lockfile;
+
+        lockfile
+            .server_jar
+            .iter()
+            .map(|jar| &jar.resolved)
+            .chain(
+                lockfile
+                    .plugins
+                    .iter()
+                    .chain(&lockfile.mods)
+                    .chain(
+                        lockfile
+                            .worlds
+                            .values()
+                            .flat_map(|w| w.download.iter().chain(&w.datapacks)),
+                    )
+                    .map(|(_, res)| res),
+            )
+            .find(|res| res.url == resolved.url && res.filename == resolved.filename)
+            .and_then(|res| {
+                Some(KnownFile {
+                    sha256: res.hashes.get("sha256")?.clone(),
+                    size: res.size,
+                })
+            })
+    }
+
+    fn progress_bar(&self, parent_progress: Option<&ProgressBar>) -> ProgressBar {
+        if let Some(parent) = parent_progress {
             self.app
                 .multi_progress
                 .insert_after(parent, ProgressBar::new_spinner())
         } else {
             self.app.multi_progress.add(ProgressBar::new_spinner())
-        };
-
-        let result = self
-            .app
-            .download(resolvable, self.output_dir.join(folder_path), progress_bar)
-            .await?;
-
-        Ok((
-            self.output_dir.join(folder_path).join(&result.filename),
-            result,
-        ))
+        }
     }
 }
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use console::style;
+use futures_util::{stream, StreamExt, TryStreamExt};
+
+use crate::{
+    app::{AddonType, App, Resolvable, ResolvedFile},
+    model::{Downloadable, LockedServerJar},
+};
+
+use super::{stages::BuildStage, BuildContext};
+
+/// A single line of a [`BuildPlan`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanEntry {
+    Added(String),
+    Changed(String, String),
+    Removed(String),
+    Unchanged(String),
+}
+
+impl PlanEntry {
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, Self::Unchanged(_))
+    }
+}
+
+/// What a build would change compared to the previous lockfile
+#[derive(Debug, Default)]
+pub struct BuildPlan {
+    pub server_jar: Option<PlanEntry>,
+    pub plugins: Vec<PlanEntry>,
+    pub mods: Vec<PlanEntry>,
+    pub files: Vec<PlanEntry>,
+}
+
+impl BuildPlan {
+    pub fn is_empty(&self) -> bool {
+        self.server_jar
+            .as_ref()
+            .map_or(true, PlanEntry::is_unchanged)
+            && [&self.plugins, &self.mods, &self.files]
+                .iter()
+                .all(|list| list.iter().all(PlanEntry::is_unchanged))
+    }
+
+    pub fn print(&self, app: &App) {
+        if let Some(entry) = &self.server_jar {
+            app.print_job("Server jar");
+            Self::print_entry(app, entry);
+        }
+
+        for (title, list) in [
+            ("Plugins", &self.plugins),
+            ("Mods", &self.mods),
+            ("Bootstrapped files", &self.files),
+        ] {
+            if list.iter().all(PlanEntry::is_unchanged) {
+                continue;
+            }
+
+            app.print_job(title);
+            for entry in list.iter().filter(|e| !e.is_unchanged()) {
+                Self::print_entry(app, entry);
+            }
+
+            let unchanged = list.iter().filter(|e| e.is_unchanged()).count();
+            if unchanged != 0 {
+                app.log(style(format!("{unchanged} unchanged")).dim());
+            }
+        }
+    }
+
+    fn print_entry(app: &App, entry: &PlanEntry) {
+        match entry {
+            PlanEntry::Added(name) => app.log(format!("{} {name}", style("+").green().bold())),
+            PlanEntry::Changed(old, new) if old == new => {
+                app.log(format!("{} {new}", style("~").yellow().bold()));
+            }
+            PlanEntry::Changed(old, new) => app.log(format!(
+                "{} {} -> {new}",
+                style("~").yellow().bold(),
+                style(old).dim()
+            )),
+            PlanEntry::Removed(name) => app.log(format!("{} {name}", style("-").red().bold())),
+            PlanEntry::Unchanged(name) => app.log(style(name).dim()),
+        }
+    }
+}
+
+impl<'a> BuildContext<'a> {
+    /// Resolve everything like [`Self::build_all`] would, without downloading
+    /// or writing anything, and print the differences to the lockfile
+    pub async fn plan(&mut self) -> Result<BuildPlan> {
+        self.app.print_job(&format!(
+            "{} {}...",
+            style("Planning").bold(),
+            style(&self.app.server.name).green().bold()
+        ));
+
+        self.reload()?;
+
+        let stages = self.stages()?;
+
+        let mut plan = BuildPlan {
+            server_jar: Some(self.plan_server_jar().await.context("Server jar")?),
+            ..Default::default()
+        };
+
+        if stages.contains(&BuildStage::Plugins) {
+            plan.plugins = self.plan_addons(AddonType::Plugin).await?;
+        }
+
+        if stages.contains(&BuildStage::Mods) {
+            plan.mods = self.plan_addons(AddonType::Mod).await?;
+        }
+
+        if stages.contains(&BuildStage::Bootstrap) {
+            plan.files = self.plan_bootstrap()?;
+        }
+
+        self.app.info(format!(
+            "Stages: {}",
+            stages
+                .iter()
+                .map(|stage| stage.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        plan.print(self.app);
+
+        if plan.is_empty() {
+            self.app.success("Nothing to do, everything is up to date");
+        } else {
+            self.app.info("Dry run, nothing was written");
+        }
+
+        Ok(plan)
+    }
+
+    async fn plan_server_jar(&mut self) -> Result<PlanEntry> {
+        // as written in server.toml, before the loader is pinned
+        let server_jar = self.app.server.jar.clone();
+        let (loader, installer) = self.lock_loader_versions().await?;
+
+        let resolved = match (self.locked, &self.lockfile.server_jar) {
+            (true, Some(locked)) => locked.resolved.clone(),
+            _ => self.app.server.jar.resolve_source(self.app).await?,
+        };
+
+        let describe = |mc_version: &str, filename: &str, loader: Option<&String>| match loader {
+            Some(loader) => format!("{filename} ({mc_version}, loader {loader})"),
+            None => format!("{filename} ({mc_version})"),
+        };
+
+        let new = describe(
+            &self.app.server.mc_version,
+            &resolved.filename,
+            loader.as_ref(),
+        );
+
+        Ok(match &self.lockfile.server_jar {
+            None => PlanEntry::Added(new),
+            Some(LockedServerJar {
+                mc_version,
+                jar,
+                loader: old_loader,
+                installer: old_installer,
+                resolved: old_resolved,
+                ..
+            }) => {
+                if mc_version == &self.app.server.mc_version
+                    && jar == &server_jar
+                    && old_loader == &loader
+                    && old_installer == &installer
+                    && old_resolved.filename == resolved.filename
+                {
+                    PlanEntry::Unchanged(new)
+                } else {
+                    PlanEntry::Changed(
+                        describe(mc_version, &old_resolved.filename, old_loader.as_ref()),
+                        new,
+                    )
+                }
+            }
+        })
+    }
+
+    async fn plan_addons(&self, addon_type: AddonType) -> Result<Vec<PlanEntry>> {
+        let mut server_list = Downloadable::expand_files(
+            self.app.active_addons(self.app.get_addons(addon_type))?,
+            &self.app.server.path,
+        )?;
+        // dependencies aren't walked here, assume they stay the same
+        server_list.extend(self.locked_dependencies(addon_type));
+        let old_list = match addon_type {
+            AddonType::Plugin => &self.lockfile.plugins,
+            AddonType::Mod => &self.lockfile.mods,
+        };
+
+        let resolved_list = if self.locked {
+            self.locked_addons(addon_type, &server_list)
+                .context("server.toml and the lockfile disagree")?
+        } else {
+            let app = &*self.app;
+            stream::iter(server_list.iter().map(|addon| async move {
+                addon
+                    .resolve_source(app)
+                    .await
+                    .with_context(|| format!("Resolving {}", addon.to_short_string()))
+            }))
+            .buffered(app.parallel_downloads())
+            .try_collect::<Vec<_>>()
+            .await?
+        };
+
+        let mut used = vec![false; old_list.len()];
+        let mut entries = vec![];
+
+        for (addon, resolved) in server_list.iter().zip(resolved_list) {
+            let old = Self::find_old_addon(old_list, &used, addon, &resolved);
+
+            entries.push(match old {
+                Some(idx) => {
+                    used[idx] = true;
+                    let old = &old_list[idx].1;
+                    // local files are hashed when resolving, so changed contents show up here
+                    let hash_changed =
+                        match (old.hashes.get("sha256"), resolved.hashes.get("sha256")) {
+                            (Some(old), Some(new)) => old != new,
+                            _ => false,
+                        };
+                    let old_filename = &old.filename;
+                    if old_filename == &resolved.filename && !hash_changed {
+                        PlanEntry::Unchanged(resolved.filename)
+                    } else {
+                        PlanEntry::Changed(old_filename.clone(), resolved.filename)
+                    }
+                }
+                None => PlanEntry::Added(resolved.filename),
+            });
+        }
+
+        for ((_, resolved), _) in old_list.iter().zip(&used).filter(|(_, used)| !**used) {
+            entries.push(PlanEntry::Removed(resolved.filename.clone()));
+        }
+
+        Ok(entries)
+    }
+
+    /// Index of the lockfile entry `addon` replaces: the exact same addon,
+    /// the same project with another version or the same filename
+    fn find_old_addon(
+        old_list: &[(Downloadable, ResolvedFile)],
+        used: &[bool],
+        addon: &Downloadable,
+        resolved: &ResolvedFile,
+    ) -> Option<usize> {
+        let unused = || old_list.iter().enumerate().filter(|(idx, _)| !used[*idx]);
+
+        unused()
+            .find(|(_, (dl, _))| dl == addon)
+            .or_else(|| unused().find(|(_, (dl, _))| dl.is_same_as(addon)))
+            .or_else(|| unused().find(|(_, (_, res))| res.filename == resolved.filename))
+            .map(|(idx, _)| idx)
+    }
+
+    fn plan_bootstrap(&self) -> Result<Vec<PlanEntry>> {
+        let old_files = self
+            .lockfile
+            .files
+            .iter()
+            .map(|f| (&f.path, f))
+            .collect::<HashMap<_, _>>();
+
+        let sources = self.bootstrap_sources()?;
+
+        let mut entries = vec![];
+        for (source, rel_path) in &sources {
+            let name = rel_path.display().to_string();
+            let modified = std::fs::metadata(source)?.modified().ok();
+            let hash = if self.should_bootstrap_file(rel_path) {
+                let contents = std::fs::read_to_string(source)
+                    .with_context(|| format!("Reading from '{}'", source.display()))?;
+                Some(App::hash_sha256(&self.bootstrap_content(&contents)))
+            } else {
+                None
+            };
+
+            let cache = old_files.get(rel_path).copied();
+            entries.push(if cache.is_none() {
+                PlanEntry::Added(name)
+            } else if self.is_bootstrapped(rel_path, modified, hash.as_deref(), cache) {
+                PlanEntry::Unchanged(name)
+            } else {
+                PlanEntry::Changed(name.clone(), name)
+            });
+        }
+
+        if !self.app.server.options.keep_stale_files {
+            for rel_path in self.stale_files(&sources) {
+                entries.push(PlanEntry::Removed(rel_path.display().to_string()));
+            }
+        }
+
+        Ok(entries)
+    }
+}
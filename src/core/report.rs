@@ -0,0 +1,272 @@
+use std::{
+    fmt::{self, Display, Formatter, Write as _},
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::{
+    app::{AddonType, App, FileOrigin, ResolvedFile},
+    interop::hooks::HookResult,
+    model::{Downloadable, LockedServerJar},
+};
+
+use super::{stages::BuildStage, BuildContext};
+
+/// Everything a build did, written with `mcman build --report`
+#[derive(Debug, Default, Serialize)]
+pub struct BuildReport {
+    pub server: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub seconds: f64,
+    pub server_jar: Option<ReportServerJar>,
+    pub addons: Vec<ReportAddon>,
+    pub files: Vec<ReportFile>,
+    pub hooks: Vec<HookResult>,
+    pub stages: Vec<ReportStage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportServerJar {
+    /// Name of the jar that gets launched
+    pub name: String,
+    #[serde(flatten)]
+    pub locked: LockedServerJar,
+    pub origin: FileOrigin,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportAddonType {
+    Plugin,
+    Mod,
+    /// The download of a world
+    World,
+    Datapack,
+}
+
+impl Display for ReportAddonType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Plugin => "plugin",
+            Self::Mod => "mod",
+            Self::World => "world",
+            Self::Datapack => "datapack",
+        })
+    }
+}
+
+impl From<AddonType> for ReportAddonType {
+    fn from(addon_type: AddonType) -> Self {
+        match addon_type {
+            AddonType::Plugin => Self::Plugin,
+            AddonType::Mod => Self::Mod,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportAddon {
+    #[serde(rename = "type")]
+    pub addon_type: ReportAddonType,
+    /// The world of world downloads and datapacks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub world: Option<String>,
+    pub source: String,
+    /// The version it resolved to, or the version in server.toml if the source has none
+    pub version: Option<String>,
+    pub filename: String,
+    /// `algorithm:hash`
+    pub hash: Option<String>,
+    pub origin: FileOrigin,
+    pub addon: Downloadable,
+}
+
+impl ReportAddon {
+    pub fn new(
+        addon_type: impl Into<ReportAddonType>,
+        addon: &Downloadable,
+        resolved: &ResolvedFile,
+        origin: FileOrigin,
+    ) -> Self {
+        Self {
+            addon_type: addon_type.into(),
+            world: None,
+            source: addon.to_short_string(),
+            version: resolved
+                .version
+                .clone()
+                .or_else(|| addon.version_selector().map(ToOwned::to_owned)),
+            filename: resolved.filename.clone(),
+            hash: App::get_best_hash(&resolved.hashes).map(|(algo, hash)| format!("{algo}:{hash}")),
+            origin,
+            addon: addon.clone(),
+        }
+    }
+
+    /// A world download or datapack of the world `name`
+    pub fn for_world(
+        addon_type: ReportAddonType,
+        name: &str,
+        addon: &Downloadable,
+        resolved: &ResolvedFile,
+        origin: FileOrigin,
+    ) -> Self {
+        Self {
+            world: Some(name.to_owned()),
+            ..Self::new(addon_type, addon, resolved, origin)
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportFile {
+    pub path: PathBuf,
+    pub status: FileStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Written,
+    Unchanged,
+    Removed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportStage {
+    pub stage: String,
+    pub seconds: f64,
+}
+
+impl ReportStage {
+    pub fn new(stage: BuildStage, took: Duration) -> Self {
+        Self {
+            stage: stage.name().to_owned(),
+            seconds: took.as_secs_f64(),
+        }
+    }
+}
+
+impl<'a> BuildContext<'a> {
+    /// Write the report to `--report` and the github step summary, if enabled
+    pub fn write_report(&self) -> Result<()> {
+        if let Some(path) = &self.report_path {
+            let writer = BufWriter::new(
+                File::create(path).context(format!("Creating report at {}", path.display()))?,
+            );
+            serde_json::to_writer_pretty(writer, &self.report)?;
+            self.app
+                .log(format!("Report written to {}", path.display()));
+        }
+
+        if self.github_summary {
+            let Ok(path) = std::env::var("GITHUB_STEP_SUMMARY") else {
+                self.app
+                    .warn("GITHUB_STEP_SUMMARY isn't set, not writing a step summary");
+                return Ok(());
+            };
+
+            let mut f = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .context("Opening GITHUB_STEP_SUMMARY")?;
+            f.write_all(self.report.to_markdown().as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl BuildReport {
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+
+        let _ = writeln!(
+            md,
+            "### {} `{}` in {:.1}s\n",
+            if self.success {
+                "Built"
+            } else {
+                "Failed to build"
+            },
+            self.server,
+            self.seconds
+        );
+
+        if let Some(error) = &self.error {
+            let _ = writeln!(md, "```\n{error}\n```\n");
+        }
+
+        if let Some(jar) = &self.server_jar {
+            let _ = writeln!(
+                md,
+                "Server jar: `{}` ({} {}, {})\n",
+                jar.locked.resolved.filename,
+                jar.locked.jar.to_string(),
+                jar.locked.mc_version,
+                origin_name(jar.origin)
+            );
+        }
+
+        if !self.addons.is_empty() {
+            md.push_str("| Type | Source | Version | File | Origin |\n");
+            md.push_str("| --- | --- | --- | --- | --- |\n");
+            for addon in &self.addons {
+                let addon_type = match &addon.world {
+                    Some(world) => format!("{} ({world})", addon.addon_type),
+                    None => addon.addon_type.to_string(),
+                };
+                let _ = writeln!(
+                    md,
+                    "| {addon_type} | {} | {} | `{}` | {} |",
+                    addon.source,
+                    addon.version.as_deref().unwrap_or("-"),
+                    addon.filename,
+                    origin_name(addon.origin)
+                );
+            }
+            md.push('\n');
+        }
+
+        if !self.stages.is_empty() {
+            md.push_str("| Stage | Time |\n| --- | --- |\n");
+            for stage in &self.stages {
+                let _ = writeln!(md, "| {} | {:.1}s |", stage.stage, stage.seconds);
+            }
+            md.push('\n');
+        }
+
+        if !self.hooks.is_empty() {
+            md.push_str("| Hook | Result | Time |\n| --- | --- | --- |\n");
+            for hook in &self.hooks {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {:.1}s |",
+                    hook.name,
+                    if hook.success { "ok" } else { "failed" },
+                    hook.seconds
+                );
+            }
+            md.push('\n');
+        }
+
+        md
+    }
+}
+
+fn origin_name(origin: FileOrigin) -> &'static str {
+    match origin {
+        FileOrigin::Existing => "existing",
+        FileOrigin::Cached => "cached",
+        FileOrigin::Local => "local",
+        FileOrigin::Downloaded => "downloaded",
+    }
+}
@@ -3,7 +3,10 @@ use std::{fs::OpenOptions, io::Write};
 use anyhow::Result;
 use tokio::fs;
 
-use crate::model::{ServerType, StartupMethod};
+use crate::{
+    model::{ServerType, StartupMethod},
+    util,
+};
 
 use super::BuildContext;
 
@@ -40,6 +43,9 @@ impl<'a> BuildContext<'a> {
     }
 
     pub async fn create_scripts(&self, startup: StartupMethod) -> Result<()> {
+        util::unlink(&self.output_dir.join("start.bat"))?;
+        util::unlink(&self.output_dir.join("start.sh"))?;
+
         fs::write(
             self.output_dir.join("start.bat"),
             self.app
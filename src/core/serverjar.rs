@@ -1,21 +1,12 @@
-use std::{
-    env,
-    io::{BufRead, BufReader},
-    process::Command,
-    process::Stdio,
-    time::Duration,
-};
+use std::{path::PathBuf, time::Duration};
 
 use anyhow::{bail, Context, Result};
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
-use pathdiff::diff_paths;
-use tokio::{
-    fs::{self, File},
-    io::AsyncWriteExt,
-};
+use tokio::fs;
 
 use crate::{
+    app::{App, FileOrigin, ResolvedFile, Step},
     model::{InstallMethod, ServerType},
     sources::quilt,
 };
@@ -98,8 +89,108 @@ impl<'a> BuildContext<'a> {
         })
     }
 
-    pub async fn download_server_jar(&'a self) -> Result<String> {
-        let serverjar_name = match self.get_install_method().await? {
+    /// Resolves the (loader, installer) versions for Fabric and installer based server
+    /// types once, and pins the server jar to them so the download matches what's
+    /// recorded in the lockfile. With `--locked`, checks the lockfile against server.toml
+    /// and pins them to the locked versions instead
+    pub async fn lock_loader_versions(&mut self) -> Result<(Option<String>, Option<String>)> {
+        let (loader, installer) = if self.locked {
+            self.locked_loader_versions()?
+        } else {
+            (
+                self.resolve_loader_version().await?,
+                self.resolve_installer_version().await?,
+            )
+        };
+
+        if let Some(version) = &loader {
+            self.app.server.jar.set_loader(version);
+        }
+        if let Some(version) = &installer {
+            self.app.server.jar.set_installer(version);
+        }
+
+        Ok((loader, installer))
+    }
+
+    fn locked_loader_versions(&self) -> Result<(Option<String>, Option<String>)> {
+        let Some(locked) = &self.lockfile.server_jar else {
+            bail!("Server jar isn't in the lockfile, build without --locked to update it");
+        };
+
+        if locked.mc_version != self.app.server.mc_version || locked.jar != self.app.server.jar {
+            bail!(
+                "Server jar in lockfile ({} {}) doesn't match server.toml ({} {}), build without --locked to update it",
+                locked.jar.to_string(),
+                locked.mc_version,
+                self.app.server.jar.to_string(),
+                self.app.server.mc_version,
+            );
+        }
+
+        Ok((locked.loader.clone(), locked.installer.clone()))
+    }
+
+    pub async fn resolve_loader_version(&self) -> Result<Option<String>> {
+        Ok(match &self.app.server.jar {
+            ServerType::Quilt { loader, .. } => Some(
+                quilt::map_quilt_loader_version(&self.app.http_client, loader)
+                    .await
+                    .context("resolving quilt loader version id (latest/latest-beta)")?,
+            ),
+            ServerType::NeoForge { loader } => {
+                Some(self.app.neoforge().resolve_version(loader).await?)
+            }
+            ServerType::Forge { loader } => Some(self.app.forge().resolve_version(loader).await?),
+            ServerType::Fabric { loader, .. } => Some(match loader.as_str() {
+                "latest" => self.app.fabric().fetch_latest_loader().await?,
+                id => id.to_owned(),
+            }),
+            _ => None,
+        })
+    }
+
+    pub async fn resolve_installer_version(&self) -> Result<Option<String>> {
+        Ok(match &self.app.server.jar {
+            ServerType::Fabric { installer, .. } => Some(match installer.as_str() {
+                "latest" => self.app.fabric().fetch_latest_installer().await?,
+                id => id.to_owned(),
+            }),
+            _ => None,
+        })
+    }
+
+    /// Download the server jar or its installer, from the lockfile if `--locked`
+    pub async fn fetch_server_jar(&self) -> Result<(ResolvedFile, FileOrigin)> {
+        let (_, resolved, origin) = match (self.locked, &self.lockfile.server_jar) {
+            (true, Some(locked)) => self.download_resolved(&locked.resolved, "", None).await?,
+            _ => self.downloadable(&self.app.server.jar, "", None).await?,
+        };
+
+        Ok((resolved, origin))
+    }
+
+    /// The jar an installer produced and its sha256, so `mcman verify` can check it.
+    /// `None` if the server jar was downloaded directly
+    pub async fn installed_server_jar(
+        &self,
+        jar_name: &str,
+        resolved: &ResolvedFile,
+    ) -> Result<Option<(PathBuf, String)>> {
+        let path = self.output_dir.join(jar_name);
+        if jar_name == resolved.filename || !path.is_file() {
+            return Ok(None);
+        }
+
+        let hash = App::hash_file(&path, "sha256")
+            .await
+            .context(format!("Hashing {}", path.display()))?;
+
+        Ok(Some((PathBuf::from(jar_name), hash)))
+    }
+
+    pub async fn download_server_jar(&'a self) -> Result<(String, ResolvedFile, FileOrigin)> {
+        let (serverjar_name, resolved, origin) = match self.get_install_method().await? {
             InstallMethod::Installer {
                 name,
                 label,
@@ -107,9 +198,9 @@ impl<'a> BuildContext<'a> {
                 rename_from,
                 jar_name,
             } => {
-                let (_, resolved) = self.downloadable(&self.app.server.jar, "", None).await?;
+                let (resolved, origin) = self.fetch_server_jar().await?;
 
-                let installer_jar = resolved.filename;
+                let installer_jar = resolved.filename.clone();
 
                 let jar_name = jar_name.replace("${mcver}", &self.app.server.mc_version);
 
@@ -140,15 +231,17 @@ impl<'a> BuildContext<'a> {
                         .dim()
                     ));
 
-                    let mut cmd_args = vec!["-jar", &installer_jar];
+                    let step = Step::ExecuteJava {
+                        jar: installer_jar,
+                        args,
+                        path: self.output_dir.clone(),
+                        label: name.to_owned(),
+                        tag: label.to_owned(),
+                    };
 
-                    cmd_args.extend(args.iter().map(String::as_str));
-
-                    let java = env::var("JAVA_BIN").unwrap_or("java".to_owned());
-
-                    self.execute_child((&java, &cmd_args), name, label)
+                    self.app
+                        .run_step(&step, pb.clone())
                         .await
-                        .context(format!("Executing command: 'java {}'", cmd_args.join(" ")))
                         .context(format!("Running installer: {name}"))?;
 
                     if let Some(from) = &rename_from {
@@ -186,73 +279,14 @@ impl<'a> BuildContext<'a> {
                     pb.finish_and_clear();
                 }
 
-                jar_name
+                (jar_name, resolved, origin)
             }
             InstallMethod::SingleJar => {
-                self.downloadable(&self.app.server.jar, "", None)
-                    .await?
-                    .1
-                    .filename
+                let (resolved, origin) = self.fetch_server_jar().await?;
+                (resolved.filename.clone(), resolved, origin)
             }
         };
 
-        Ok(serverjar_name)
-    }
-
-    pub async fn execute_child(&self, cmd: (&str, &[&str]), label: &str, tag: &str) -> Result<()> {
-        // because jre cant understand UNC
-        let dir = diff_paths(&self.output_dir, env::current_dir()?.canonicalize()?).unwrap();
-
-        let args: Vec<String> = cmd.1.iter().map(|a| self.app.server.format(a)).collect();
-        self.app.dbg(args.join(" "));
-
-        let mut child = Command::new(cmd.0)
-            .args(args)
-            .current_dir(dir)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .context(format!("Running {label}"))?;
-
-        let spinner = self
-            .app
-            .multi_progress
-            .add(
-                ProgressBar::new_spinner().with_style(ProgressStyle::with_template(
-                    "    {spinner:.green} {prefix:.bold} {msg}",
-                )?),
-            );
-
-        spinner.enable_steady_tick(Duration::from_millis(200));
-        spinner.set_prefix(format!("[{tag}]"));
-
-        let mut log_file = File::create(self.output_dir.join(format!(".{tag}.mcman.log"))).await?;
-
-        log_file
-            .write_all(format!("=== mcman {tag} / {label} output ===\n\n").as_bytes())
-            .await?;
-
-        for buf in BufReader::new(child.stdout.take().unwrap()).lines() {
-            let buf = buf.context("Reading child process stdout buffer")?;
-            let buf = buf.trim();
-
-            if !buf.is_empty() {
-                log_file.write_all(buf.as_bytes()).await?;
-                log_file.write_all(b"\n").await?;
-
-                if let Some(last_line) = buf.split('\n').last() {
-                    spinner.set_message(last_line.to_string());
-                }
-            }
-        }
-
-        if !child.wait()?.success() {
-            bail!("{label} exited with non-zero code");
-        }
-
-        spinner.disable_steady_tick();
-        spinner.finish_and_clear();
-
-        Ok(())
+        Ok((serverjar_name, resolved, origin))
     }
 }
@@ -0,0 +1,179 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    time::Instant,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{app::AddonType, model::LockedServerJar};
+
+use super::{
+    report::{ReportServerJar, ReportStage},
+    BuildContext,
+};
+
+/// The stages of a build, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildStage {
+    ServerJar,
+    Plugins,
+    Mods,
+    Worlds,
+    Bootstrap,
+    Scripts,
+}
+
+impl BuildStage {
+    pub const ALL: [Self; 6] = [
+        Self::ServerJar,
+        Self::Plugins,
+        Self::Mods,
+        Self::Worlds,
+        Self::Bootstrap,
+        Self::Scripts,
+    ];
+
+    /// The name used with `--skip`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ServerJar => "serverjar",
+            Self::Plugins => "plugins",
+            Self::Mods => "mods",
+            Self::Worlds => "worlds",
+            Self::Bootstrap => "bootstrap",
+            Self::Scripts => "scripts",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+
+    pub fn is_skippable(self) -> bool {
+        self != Self::ServerJar
+    }
+}
+
+impl Display for BuildStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl<'a> BuildContext<'a> {
+    /// The stages a build would run, without the skipped ones and ones with nothing to do
+    pub fn stages(&self) -> Result<Vec<BuildStage>> {
+        let mut skipped = vec![];
+        for name in &self.skip_stages {
+            let Some(stage) = BuildStage::from_name(name) else {
+                bail!(
+                    "Unknown stage '{name}', the stages are: {}",
+                    BuildStage::ALL
+                        .iter()
+                        .filter(|s| s.is_skippable())
+                        .map(|s| s.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            };
+
+            if !stage.is_skippable() {
+                bail!("The {stage} stage can't be skipped");
+            }
+
+            skipped.push(stage);
+        }
+
+        Ok(BuildStage::ALL
+            .into_iter()
+            .filter(|stage| !skipped.contains(stage) && self.has_work(*stage))
+            .collect())
+    }
+
+    fn has_work(&self, stage: BuildStage) -> bool {
+        match stage {
+            BuildStage::Worlds => !self.app.server.worlds.is_empty(),
+            BuildStage::Bootstrap => {
+                self.bootstrap_folders().iter().any(|f| f.exists())
+                    || !self.lockfile.files.is_empty()
+            }
+            BuildStage::Scripts => !self.app.server.launcher.disable,
+            _ => true,
+        }
+    }
+
+    /// Run `stages` in order, returns the server jar's name
+    pub async fn run_stages(&mut self, stages: &[BuildStage]) -> Result<String> {
+        let mut done = vec![];
+        let mut server_jar = None;
+
+        for &stage in stages {
+            let started = Instant::now();
+
+            self.run_stage(stage, &mut server_jar)
+                .await
+                .context(format!("Stage: {stage}"))?;
+
+            self.app
+                .dbg(format!("stage {stage} took {:?}", started.elapsed()));
+            self.report
+                .stages
+                .push(ReportStage::new(stage, started.elapsed()));
+            done.push(stage);
+        }
+
+        if !done.contains(&BuildStage::Bootstrap) {
+            // keep track of what was bootstrapped before
+            self.new_lockfile.files.clone_from(&self.lockfile.files);
+        }
+
+        server_jar.context("The serverjar stage didn't run")
+    }
+
+    async fn run_stage(
+        &mut self,
+        stage: BuildStage,
+        server_jar: &mut Option<String>,
+    ) -> Result<()> {
+        match stage {
+            BuildStage::ServerJar => {
+                self.app.ci("::group::Server Jar");
+                let jar = self.app.server.jar.clone();
+                let (loader, installer) = self.lock_loader_versions().await?;
+                let (jar_name, resolved_jar, origin) = self.download_server_jar().await?;
+                let installed_jar = self.installed_server_jar(&jar_name, &resolved_jar).await?;
+                let locked = LockedServerJar {
+                    mc_version: self.app.server.mc_version.clone(),
+                    jar,
+                    loader,
+                    installer,
+                    resolved: resolved_jar,
+                    installed: installed_jar,
+                };
+                self.new_lockfile.server_jar = Some(locked.clone());
+                self.report.server_jar = Some(ReportServerJar {
+                    name: jar_name.clone(),
+                    locked,
+                    origin,
+                });
+                *server_jar = Some(jar_name);
+                self.app.ci("::endgroup::");
+            }
+            BuildStage::Plugins => self.download_addons(AddonType::Plugin).await?,
+            BuildStage::Mods => self.download_addons(AddonType::Mod).await?,
+            BuildStage::Worlds => self.process_worlds().await?,
+            BuildStage::Bootstrap => self.bootstrap_files().await?,
+            BuildStage::Scripts => {
+                let startup = self
+                    .get_startup_method(server_jar.as_deref().unwrap_or_default())
+                    .await?;
+
+                self.create_scripts(startup).await?;
+
+                self.app.log("start.bat and start.sh created");
+            }
+        }
+
+        Ok(())
+    }
+}
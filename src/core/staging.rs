@@ -0,0 +1,117 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use walkdir::WalkDir;
+
+use super::BuildContext;
+
+/// `server` => `server.<suffix>`, next to the output directory
+pub fn sibling_dir(output_dir: &Path, suffix: &str) -> Result<PathBuf> {
+    let name = output_dir
+        .file_name()
+        .ok_or(anyhow!(
+            "Output directory '{}' has no name",
+            output_dir.display()
+        ))?
+        .to_string_lossy();
+
+    Ok(output_dir.with_file_name(format!("{name}.{suffix}")))
+}
+
+/// Recreate the tree at `from` in `to`. Files are copied, since hooks, installers and
+/// the server write to them in place, except under `linked` where they're hardlinked
+/// (or copied if that fails)
+pub fn stage_tree(from: &Path, to: &Path, linked: &[PathBuf]) -> Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry.context("Walking output directory")?;
+        let rel_path = entry.path().strip_prefix(from)?;
+        let dest = to.join(rel_path);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest).context(format!("Creating {}", dest.display()))?;
+            continue;
+        }
+
+        let link = linked.iter().any(|dir| rel_path.starts_with(dir));
+        if !link || fs::hard_link(entry.path(), &dest).is_err() {
+            fs::copy(entry.path(), &dest).context(format!(
+                "Copying '{}' to '{}'",
+                entry.path().display(),
+                dest.display()
+            ))?;
+        }
+    }
+
+    Ok(())
+}
+
+impl<'a> BuildContext<'a> {
+    /// Switch `output_dir` to a staging directory with a copy of the current output.
+    /// Worlds are hardlinked to save space, so they're shared with the previous build
+    pub async fn begin_staging(&mut self) -> Result<()> {
+        let live = self.output_dir.clone();
+        let staging = sibling_dir(&live, "staging")?;
+
+        if staging.exists() {
+            self.app.dbg("removing leftover staging directory");
+            tokio::fs::remove_dir_all(&staging)
+                .await
+                .context("Removing leftover staging directory")?;
+        }
+
+        if live.exists() {
+            let (from, to) = (live.clone(), staging.clone());
+            let worlds = self
+                .app
+                .server
+                .worlds
+                .keys()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>();
+            tokio::task::spawn_blocking(move || stage_tree(&from, &to, &worlds))
+                .await?
+                .context("Creating staging directory")?;
+        }
+
+        self.app.dbg(format!("staging in {}", staging.display()));
+
+        self.live_output_dir = Some(live);
+        self.output_dir = staging;
+
+        Ok(())
+    }
+
+    /// Move the staged build into place, keeping the current one as the previous build
+    pub async fn finish_staging(&mut self) -> Result<()> {
+        let Some(live) = self.live_output_dir.take() else {
+            return Ok(());
+        };
+        let previous = sibling_dir(&live, "previous")?;
+
+        if previous.exists() {
+            tokio::fs::remove_dir_all(&previous)
+                .await
+                .context("Removing previous build")?;
+        }
+
+        if live.exists() {
+            tokio::fs::rename(&live, &previous)
+                .await
+                .context("Moving current build aside")?;
+        }
+
+        if let Err(e) = tokio::fs::rename(&self.output_dir, &live).await {
+            let _ = tokio::fs::rename(&previous, &live).await;
+            return Err(e).context("Moving staged build into place");
+        }
+
+        self.output_dir = live;
+        self.lockfile.path = self.output_dir.join(".mcman.lock");
+        self.new_lockfile.path = self.output_dir.join(".mcman.lock");
+
+        Ok(())
+    }
+}
@@ -5,15 +5,22 @@ use std::{
     time::Duration,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use futures_util::{stream, StreamExt, TryStreamExt};
 use indicatif::{ProgressBar, ProgressIterator, ProgressStyle};
 
-use crate::model::World;
+use crate::{
+    app::{FileOrigin, ResolvedFile},
+    model::{Downloadable, LockedWorld, World},
+};
 
-use super::BuildContext;
+use super::{
+    report::{ReportAddon, ReportAddonType},
+    BuildContext,
+};
 
 impl<'a> BuildContext<'a> {
-    pub async fn process_worlds(&self) -> Result<()> {
+    pub async fn process_worlds(&mut self) -> Result<()> {
         let progress_bar = self.app.multi_progress.add(
             ProgressBar::new(self.app.server.worlds.len() as u64)
                 .with_style(ProgressStyle::with_template(
@@ -24,18 +31,19 @@ impl<'a> BuildContext<'a> {
 
         self.app.ci("::group::Worlds");
 
-        for (name, world) in self
-            .app
-            .server
-            .worlds
-            .iter()
-            .progress_with(progress_bar.clone())
-        {
+        let worlds = self.app.server.worlds.clone();
+        for (name, world) in worlds.iter().progress_with(progress_bar.clone()) {
             progress_bar.set_message(name.clone());
 
-            self.process_world(&progress_bar, name, world)
+            let (locked, report) = self
+                .process_world(&progress_bar, name, world)
                 .await
                 .context(format!("Processing world: {name}"))?;
+            self.report.addons.extend(report);
+
+            if locked.download.is_some() || !locked.datapacks.is_empty() {
+                self.new_lockfile.worlds.insert(name.clone(), locked);
+            }
         }
 
         self.app.ci("::endgroup::");
@@ -43,19 +51,42 @@ impl<'a> BuildContext<'a> {
         Ok(())
     }
 
+    /// Unpack or download the world if it isn't in the output yet and add its datapacks,
+    /// returns what to lock and report
     pub async fn process_world(
         &self,
         progress_bar: &ProgressBar,
         name: &str,
         world: &World,
-    ) -> Result<()> {
+    ) -> Result<(LockedWorld, Vec<ReportAddon>)> {
+        let old = self.lockfile.worlds.get(name);
+        let mut locked = LockedWorld {
+            // keep the locked download of worlds that are already unpacked
+            download: old
+                .and_then(|w| w.download.clone())
+                .filter(|(dl, _)| world.download.as_ref() == Some(dl)),
+            datapacks: vec![],
+        };
+        let mut report = vec![];
+
         if !self.world_exists_in_output(name)? {
             if self.world_source_exists(name) {
                 self.app.worlds().unpack(name)?;
             } else if let Some(dl) = &world.download {
-                let (path, _resolved) = self
-                    .downloadable(dl, ".mcman-cache", Some(progress_bar))
-                    .await?;
+                let (path, resolved, origin) = if self.locked {
+                    let Some((_, resolved)) = &locked.download else {
+                        bail!(
+                            "World download {} is not in the lockfile",
+                            dl.to_short_string()
+                        );
+                    };
+
+                    self.download_resolved(resolved, ".mcman-cache", Some(progress_bar))
+                        .await?
+                } else {
+                    self.downloadable(dl, ".mcman-cache", Some(progress_bar))
+                        .await?
+                };
 
                 let spinner = self.app.multi_progress.insert_after(
                     progress_bar,
@@ -67,19 +98,47 @@ impl<'a> BuildContext<'a> {
                 unzip(&path, &self.output_dir.join(name))?;
 
                 spinner.finish_with_message("Unzipped world successfully".to_string());
+
+                report.push(ReportAddon::for_world(
+                    ReportAddonType::World,
+                    name,
+                    dl,
+                    &resolved,
+                    origin,
+                ));
+                locked.download = Some((dl.clone(), resolved));
             }
+        } else if let Some((dl, resolved)) = &locked.download {
+            report.push(ReportAddon::for_world(
+                ReportAddonType::World,
+                name,
+                dl,
+                resolved,
+                FileOrigin::Existing,
+            ));
         }
 
         if !world.datapacks.is_empty() {
             fs::create_dir_all(self.output_dir.join(name).join("datapacks"))
                 .context(format!("Failed to create {name}/datapacks directory"))?;
 
-            self.process_datapacks(progress_bar, name, world)
+            for (dp, resolved, origin) in self
+                .process_datapacks(progress_bar, name, world)
                 .await
-                .context("Processing datapacks")?;
+                .context("Processing datapacks")?
+            {
+                report.push(ReportAddon::for_world(
+                    ReportAddonType::Datapack,
+                    name,
+                    &dp,
+                    &resolved,
+                    origin,
+                ));
+                locked.datapacks.push((dp, resolved));
+            }
         }
 
-        Ok(())
+        Ok((locked, report))
     }
 
     pub fn world_source_exists(&self, name: &str) -> bool {
@@ -104,22 +163,70 @@ impl<'a> BuildContext<'a> {
         progress_bar: &ProgressBar,
         name: &str,
         world: &World,
-    ) -> Result<()> {
+    ) -> Result<Vec<(Downloadable, ResolvedFile, FileOrigin)>> {
+        let datapacks = Downloadable::expand_files(
+            self.app.active_addons(world.datapacks.clone())?,
+            &self.app.server.path,
+        )?;
+
+        let locked_list = if self.locked {
+            Some(self.locked_datapacks(name, &datapacks)?)
+        } else {
+            None
+        };
+
         let pb = self.app.multi_progress.insert_after(
             progress_bar,
-            ProgressBar::new(world.datapacks.len() as u64)
+            ProgressBar::new(datapacks.len() as u64)
                 .with_style(ProgressStyle::with_template(
                     "{msg} [{wide_bar:.cyan/blue}] {pos}/{len}",
                 )?)
                 .with_message("Processing datapacks..."),
         );
 
-        for dp in world.datapacks.iter().progress_with(pb.clone()) {
-            let path = format!("{name}/datapacks");
-            self.downloadable(dp, &path, Some(&pb)).await?;
-        }
+        let path = format!("{name}/datapacks");
+        let (path, pb, locked_list) = (&path, &pb, locked_list.as_ref());
 
-        Ok(())
+        let resolved = stream::iter(datapacks.iter().enumerate().map(|(idx, dp)| async move {
+            let (_path, resolved, origin) = if let Some(locked) = locked_list {
+                self.download_resolved(&locked[idx], path, Some(pb)).await?
+            } else {
+                self.downloadable(dp, path, Some(pb)).await?
+            };
+
+            Ok::<_, anyhow::Error>((dp.clone(), resolved, origin))
+        }))
+        .buffered(self.app.parallel_downloads())
+        .inspect(|_| pb.inc(1))
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        pb.finish_and_clear();
+
+        Ok(resolved)
+    }
+
+    /// The locked `ResolvedFile` for every datapack of the world, in the same order
+    fn locked_datapacks(&self, name: &str, list: &[Downloadable]) -> Result<Vec<ResolvedFile>> {
+        let locked = self
+            .lockfile
+            .worlds
+            .get(name)
+            .map(|w| w.datapacks.as_slice())
+            .unwrap_or_default();
+
+        list.iter()
+            .map(|datapack| {
+                locked
+                    .iter()
+                    .find(|(dl, _)| dl == datapack)
+                    .map(|(_, resolved)| resolved.clone())
+                    .ok_or(anyhow!(
+                        "Datapack {} of world {name} is not in the lockfile",
+                        datapack.to_short_string()
+                    ))
+            })
+            .collect()
     }
 }
 
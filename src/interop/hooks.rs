@@ -1,8 +1,14 @@
-use std::{collections::HashMap, env, process::Stdio, time::Duration};
+use std::{
+    collections::HashMap,
+    env,
+    process::Stdio,
+    time::{Duration, Instant},
+};
 
 use anyhow::{bail, Context, Result};
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
 use tokio::{io::AsyncBufReadExt, process::Command};
 
 use crate::{
@@ -12,6 +18,15 @@ use crate::{
 
 pub struct HooksAPI<'a>(pub &'a App);
 
+/// How a hook went, for build reports
+#[derive(Debug, Clone, Serialize)]
+pub struct HookResult {
+    pub name: String,
+    pub event: HookEvent,
+    pub success: bool,
+    pub seconds: f64,
+}
+
 impl<'a> HooksAPI<'a> {
     pub fn resolve_filename(&self, entry: &str) -> String {
         let hook = self
@@ -30,7 +45,13 @@ impl<'a> HooksAPI<'a> {
         .unwrap_or(String::from(entry))
     }
 
-    pub async fn event(&self, event: HookEvent, data: HashMap<String, String>) -> Result<()> {
+    /// Run the hooks of `event`, adding how each went to `results` even when one fails
+    pub async fn event(
+        &self,
+        event: HookEvent,
+        data: HashMap<String, String>,
+        results: &mut Vec<HookResult>,
+    ) -> Result<()> {
         for (name, hook) in self.0.server.hooks.iter().chain(
             self.0
                 .network
@@ -64,6 +85,8 @@ impl<'a> HooksAPI<'a> {
                     self.0.log_dev(format!("Running {filename}"));
                 }
 
+                let started = Instant::now();
+
                 let mut cmd = Command::new(path);
                 cmd.kill_on_drop(true)
                     .current_dir(&self.0.server.path)
@@ -92,6 +115,14 @@ impl<'a> HooksAPI<'a> {
                     .await
                     .context(format!("waiting hook {filename}"))?;
                 spinner.finish_and_clear();
+
+                results.push(HookResult {
+                    name: filename.clone(),
+                    event: event.clone(),
+                    success: status.success(),
+                    seconds: started.elapsed().as_secs_f64(),
+                });
+
                 if status.success() {
                     self.0.success(format!("Hook {filename}"));
                 } else {
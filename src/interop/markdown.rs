@@ -219,7 +219,7 @@ impl<'a> MarkdownAPI<'a> {
         {
             pb.set_message(addon.to_string());
             table.add_from_map(
-                self.fetch_downloadable_info(addon)
+                self.fetch_addon_info(addon)
                     .await
                     .context(format!("Rendering addon: {addon:#?}"))?,
             );
@@ -255,19 +255,36 @@ impl<'a> MarkdownAPI<'a> {
 
         for datapack in world.datapacks.iter().progress_with(pb.clone()) {
             pb.set_message(datapack.to_string());
-            table.add_from_map(self.fetch_downloadable_info(datapack).await?);
+            table.add_from_map(self.fetch_addon_info(datapack).await?);
         }
 
         Ok(table)
     }
 
+    /// Like [`Self::fetch_downloadable_info`] but marks addons whose `when` doesn't match
+    pub async fn fetch_addon_info(
+        &self,
+        dl: &Downloadable,
+    ) -> Result<IndexMap<Cow<'static, str>, String>> {
+        let mut map = self.fetch_downloadable_info(dl).await?;
+
+        if let Some(reason) = dl.skip_reason(self.0)? {
+            map.insert(
+                Cow::Borrowed("Name"),
+                format!("**(Skipped: {reason})** {}", map["Name"]),
+            );
+        }
+
+        Ok(map)
+    }
+
     #[allow(clippy::too_many_lines)]
     pub async fn fetch_downloadable_info(
         &self,
         dl: &Downloadable,
     ) -> Result<IndexMap<Cow<'static, str>, String>> {
         let (name, description, version) = match dl {
-            Downloadable::Modrinth { id, version } => {
+            Downloadable::Modrinth { id, version, .. } => {
                 let proj = self.0.modrinth().fetch_project(id).await?;
 
                 (
@@ -277,13 +294,23 @@ impl<'a> MarkdownAPI<'a> {
                 )
             }
 
-            Downloadable::CurseRinth { id, version } => {
+            Downloadable::CurseRinth { id, version, .. } => {
                 let proj = self.0.curserinth().fetch_project(id).await?;
 
                 (format!("{} <sup>[CF](https://www.curseforge.com/minecraft/mc-mods/{id}) [CR](https://curserinth.kuylar.dev/mod/{id})</sup>", proj.title, id = proj.slug), sanitize(&proj.description)?, version.clone())
             }
 
-            Downloadable::Spigot { id, version } => {
+            Downloadable::CurseForge { id, version, .. } => {
+                let proj = self.0.curseforge().fetch_project(id).await?;
+
+                (
+                    format!("[{}]({})", proj.name, proj.links.website_url),
+                    sanitize(&proj.summary)?,
+                    version.clone(),
+                )
+            }
+
+            Downloadable::Spigot { id, version, .. } => {
                 let (name, desc) = self.0.spigot().fetch_info(id).await?;
 
                 (
@@ -293,7 +320,7 @@ impl<'a> MarkdownAPI<'a> {
                 )
             }
 
-            Downloadable::Hangar { id, version } => {
+            Downloadable::Hangar { id, version, .. } => {
                 let proj = mcapi::hangar::fetch_project(&self.0.http_client, id).await?;
 
                 (
@@ -307,7 +334,9 @@ impl<'a> MarkdownAPI<'a> {
                 )
             }
 
-            Downloadable::GithubRelease { repo, tag, asset } => {
+            Downloadable::GithubRelease {
+                repo, tag, asset, ..
+            } => {
                 let desc = self.0.github().fetch_repo_description(repo).await?;
 
                 (
@@ -317,11 +346,63 @@ impl<'a> MarkdownAPI<'a> {
                 )
             }
 
+            Downloadable::Gitlab {
+                url,
+                repo,
+                tag,
+                asset,
+                ..
+            } => {
+                let desc = self
+                    .0
+                    .gitlab()
+                    .fetch_description(url.as_deref(), repo)
+                    .await?;
+
+                (
+                    dl.get_md_link(),
+                    sanitize(&desc)?,
+                    format!("{tag} / `{asset}`"),
+                )
+            }
+
+            Downloadable::Gitea {
+                url,
+                repo,
+                tag,
+                asset,
+                ..
+            } => {
+                let desc = self.0.gitea().fetch_description(url, repo).await?;
+
+                (
+                    dl.get_md_link(),
+                    sanitize(&desc)?,
+                    format!("{tag} / `{asset}`"),
+                )
+            }
+
+            Downloadable::GithubActions {
+                repo,
+                run,
+                artifact,
+                ..
+            } => {
+                let desc = self.0.github().fetch_repo_description(repo).await?;
+
+                (
+                    dl.get_md_link(),
+                    sanitize(&desc)?,
+                    format!("{run} / `{artifact}`"),
+                )
+            }
+
             Downloadable::Jenkins {
                 url,
                 job,
                 build,
                 artifact,
+                ..
             } => {
                 let desc = self.0.jenkins().fetch_description(url, job).await?;
 
@@ -336,10 +417,17 @@ impl<'a> MarkdownAPI<'a> {
                 version, artifact, ..
             } => (artifact.clone(), dl.get_md_link(), version.clone()),
 
+            Downloadable::File { path, .. } => (
+                format!("`{path}`"),
+                "*Local file*".to_owned(),
+                String::new(),
+            ),
+
             Downloadable::Url {
                 url,
                 filename,
                 desc,
+                ..
             } => (
                 format!(
                     "`{}`",
@@ -11,7 +11,7 @@ use zip::{read::ZipFile, write::FileOptions, ZipArchive, ZipWriter};
 
 use crate::{
     app::{App, Prefix, Resolvable},
-    model::Downloadable,
+    model::{DownloadOptions, Downloadable},
 };
 
 pub struct MRPackInterop<'a>(pub &'a mut App);
@@ -49,6 +49,9 @@ impl<'a> MRPackInterop<'a> {
                     Some(Downloadable::Modrinth {
                         id: ver.project_id.clone(),
                         version: ver.id.clone(),
+                        channel: None,
+                        game_versions: vec![],
+                        options: DownloadOptions::default(),
                     })
                 } else {
                     None
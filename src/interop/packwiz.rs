@@ -16,7 +16,7 @@ use walkdir::WalkDir;
 
 use crate::{
     app::{AddonType, App, CacheStrategy, Prefix, ProgressPrefix, Resolvable, ResolvedFile},
-    model::Downloadable,
+    model::{DownloadOptions, Downloadable},
     util::env::try_get_url,
 };
 
@@ -133,6 +133,8 @@ impl<'a> PackwizInterop<'a> {
                                         .to_owned(),
                                         file.hash.clone(),
                                     )]),
+                                    version: None,
+                                    mirrors: vec![],
                                 },
                                 output_path.parent().unwrap().to_path_buf(),
                                 self.0
@@ -187,6 +189,9 @@ impl<'a> PackwizInterop<'a> {
                 .map(|ver| Downloadable::Modrinth {
                     id: ver.project_id.clone(),
                     version: ver.id.clone(),
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
                 })
                 .ok())
         }
@@ -198,11 +203,17 @@ impl<'a> PackwizInterop<'a> {
                 Some(Downloadable::Modrinth {
                     id: mr.mod_id.clone(),
                     version: mr.version.clone(),
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
                 })
             } else if let Some(cf) = &upd.curseforge {
                 Some(Downloadable::CurseRinth {
                     id: cf.project_id.to_string(),
                     version: cf.file_id.to_string(),
+                    channel: None,
+                    game_versions: vec![],
+                    options: DownloadOptions::default(),
                 })
             } else {
                 // TODO clarify
@@ -398,7 +409,7 @@ impl<'a> PackwizInterop<'a> {
 
     pub fn get_mod_update(dl: &Downloadable) -> Option<ModUpdate> {
         match dl {
-            Downloadable::Modrinth { id, version } => Some(ModUpdate {
+            Downloadable::Modrinth { id, version, .. } => Some(ModUpdate {
                 modrinth: Some(rpackwiz::model::ModrinthModUpdate {
                     mod_id: id.clone(),
                     version: version.clone(),
@@ -34,7 +34,7 @@ impl<'a> WorldsAPI<'a> {
             bail!("worlds/{world}.zip doesnt exist");
         }
 
-        self.unzip(&zip_path, &self.0.server.path.join("server").join(world))?;
+        self.unzip(&zip_path, &self.0.server.output_dir().join(world))?;
 
         spinner.finish();
         self.0.notify(Prefix::Unpacked, format!("world {world}"));
@@ -78,7 +78,7 @@ impl<'a> WorldsAPI<'a> {
 
         spinner.enable_steady_tick(Duration::from_millis(250));
 
-        let input_path = self.0.server.path.join("server").join(world);
+        let input_path = self.0.server.output_dir().join(world);
         let output_path = self
             .0
             .server
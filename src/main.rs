@@ -22,6 +22,10 @@ use std::env;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Apply a profile from server.toml
+    #[arg(long, global = true, value_name = "name")]
+    profile: Option<String>,
 }
 
 #[derive(clap::Subcommand)]
@@ -30,15 +34,23 @@ enum Commands {
     Init(commands::init::Args),
 
     /// Build using server.toml configuration
-    Build(commands::build::BuildArgs),
+    Build(commands::build::Args),
     /// Test the server (stops it when it ends startup)
     Run(commands::run::RunArgs),
     /// Start a development session
     Dev(commands::dev::DevArgs),
+    /// Swap the output with the build before the last --atomic build
+    Rollback(commands::rollback::Args),
+    /// Check the output against the hashes in the lockfile
+    Verify(commands::verify::Args),
 
     /// Add a plugin/mod/datapack
     #[command(subcommand)]
     Add(commands::add::Commands),
+    /// Pin addons to their newest versions
+    Update(commands::update::Args),
+    /// Pin the hashes of addons that don't have one
+    Hash,
     /// Pull files from server/ to config/
     Pull(commands::pull::Args),
     /// Helpers for setting up the environment
@@ -105,14 +117,22 @@ async fn main() -> Result<()> {
         c => {
             let mut app = base_app.upgrade()?;
 
+            if let Some(profile) = &args.profile {
+                app.apply_profile(profile)?;
+            }
+
             match c {
                 // Build
                 Commands::Build(args) => commands::build::run(app, args).await,
                 Commands::Run(args) => commands::run::run(app, args).await,
                 Commands::Dev(args) => commands::dev::run(app, args).await,
+                Commands::Rollback(args) => commands::rollback::run(&app, args),
+                Commands::Verify(args) => commands::verify::run(&app, args).await,
 
                 // Management
                 Commands::Add(commands) => commands::add::run(app, commands).await,
+                Commands::Update(args) => commands::update::run(app, args).await,
+                Commands::Hash => commands::hash::run(app).await,
                 Commands::Import(subcommands) => commands::import::run(app, subcommands).await,
                 Commands::Export(commands) => commands::export::run(app, commands).await,
                 Commands::Markdown => commands::markdown::run(app).await,
@@ -122,7 +142,10 @@ async fn main() -> Result<()> {
                 Commands::Eject => commands::eject::run(&app),
 
                 // Utils
-                Commands::Info => commands::info::run(&app),
+                Commands::Info => {
+                    commands::info::run(&app);
+                    Ok(())
+                }
                 Commands::Download(args) => commands::download::run(app, args).await,
 
                 _ => unreachable!(),
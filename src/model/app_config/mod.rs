@@ -17,6 +17,14 @@ pub struct Services {
 pub struct Sources {
     #[config(nested)]
     pub github: GithubSource,
+    #[config(nested)]
+    pub curseforge: CurseForgeSource,
+    #[config(nested)]
+    pub gitlab: GitlabSource,
+    #[config(nested)]
+    pub gitea: GiteaSource,
+    #[config(nested)]
+    pub maven: MavenSource,
 }
 
 #[derive(Debug, Serialize, Deserialize, Config)]
@@ -27,6 +35,63 @@ pub struct GithubSource {
     pub api_url: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Config)]
+pub struct CurseForgeSource {
+    /// Get one from <https://console.curseforge.com>
+    #[config(env = "CURSEFORGE_API_KEY")]
+    pub api_key: Option<String>,
+    #[config(env = "CURSEFORGE_API_URL", default = "https://api.curseforge.com")]
+    pub api_url: String,
+}
+
+/// The token is only sent to the instance at `url`
+#[derive(Debug, Serialize, Deserialize, Config)]
+pub struct GitlabSource {
+    #[config(env = "GITLAB_TOKEN")]
+    pub api_token: Option<String>,
+    #[config(env = "GITLAB_URL", default = "https://gitlab.com")]
+    pub url: String,
+}
+
+/// The token is only sent to the instance at `url`, works for Forgejo too
+#[derive(Debug, Serialize, Deserialize, Config)]
+pub struct GiteaSource {
+    #[config(env = "GITEA_TOKEN")]
+    pub api_token: Option<String>,
+    #[config(env = "GITEA_URL")]
+    pub url: Option<String>,
+}
+
+/// Credentials are only sent to urls starting with the repository's `url`
+#[derive(Debug, Serialize, Deserialize, Config)]
+pub struct MavenSource {
+    #[config(env = "MAVEN_URL")]
+    pub url: Option<String>,
+    #[config(env = "MAVEN_USERNAME")]
+    pub username: Option<String>,
+    #[config(env = "MAVEN_PASSWORD")]
+    pub password: Option<String>,
+    /// More private repositories besides the one in `url`
+    #[config(default = [])]
+    pub repositories: Vec<MavenRepository>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MavenRepository {
+    pub url: String,
+    pub username: String,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+/// Files from urls starting with `from` are also downloaded from `to`,
+/// `from` can leave out the `https://`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Mirror {
+    pub from: String,
+    pub to: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Config)]
 pub struct AppConfig {
     #[config(default = [])]
@@ -37,4 +102,13 @@ pub struct AppConfig {
     pub sources: Sources,
     #[config(env = "JAVA_BIN", default = "java")]
     pub default_java: String,
+    /// Max amount of addons resolved and downloaded at the same time
+    #[config(env = "MCMAN_PARALLEL_DOWNLOADS", default = 8)]
+    pub parallel_downloads: usize,
+    /// How many times a failed download is retried
+    #[config(env = "MCMAN_STEP_RETRIES", default = 2)]
+    pub step_retries: usize,
+    /// Tried in order when a file download fails, API requests don't use them
+    #[config(default = [])]
+    pub mirrors: Vec<Mirror>,
 }
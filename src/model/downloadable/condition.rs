@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+
+use crate::app::App;
+
+use super::{parse_version, Downloadable};
+
+/// The `when` of an addon, every field that's set has to match the server
+#[derive(Debug, Deserialize, Serialize, Clone, Hash, PartialEq, Eq, Default)]
+pub struct Condition {
+    /// Server types or loaders like `paper`, `folia` or `fabric`, any of them
+    #[serde(default)]
+    #[serde(skip_serializing_if = "crate::util::is_default")]
+    pub loader: Vec<String>,
+    /// A requirement like `>=1.21`, or a version like `1.20` for every 1.20.x
+    #[serde(default)]
+    #[serde(skip_serializing_if = "crate::util::is_default")]
+    pub mc_version: Option<String>,
+    /// Variables that have to have these values
+    #[serde(default)]
+    #[serde(skip_serializing_if = "crate::util::is_default")]
+    pub variables: BTreeMap<String, String>,
+    /// Profiles, any of them has to be applied
+    #[serde(default)]
+    #[serde(skip_serializing_if = "crate::util::is_default")]
+    pub profile: Vec<String>,
+}
+
+impl Condition {
+    /// Why this doesn't match the server, `None` if it does
+    pub fn unmet(&self, app: &App) -> Result<Option<String>> {
+        if !self.loader.is_empty() {
+            let loader = app.server.jar.get_modrinth_name();
+
+            if !loader.is_some_and(|l| self.loader.iter().any(|w| w.eq_ignore_ascii_case(l))) {
+                return Ok(Some(format!(
+                    "loader is {}, not {}",
+                    loader.unwrap_or("unknown"),
+                    self.loader.join("/")
+                )));
+            }
+        }
+
+        if let Some(requirement) = &self.mc_version {
+            let req = if requirement.contains(['^', '~', '<', '>', '=', '*', ',']) {
+                VersionReq::parse(requirement)
+            } else {
+                VersionReq::parse(&format!("={requirement}"))
+            }
+            .context(format!("Invalid mc_version condition '{requirement}'"))?;
+
+            let mc_version = app.mc_version();
+            if !parse_version(mc_version).is_some_and(|v| req.matches(&v)) {
+                return Ok(Some(format!(
+                    "mc_version {mc_version} doesn't match {requirement}"
+                )));
+            }
+        }
+
+        for (name, value) in &self.variables {
+            let actual = app.var(name);
+
+            if actual.as_ref() != Some(value) {
+                return Ok(Some(format!(
+                    "{name} is {}, not {value}",
+                    actual.as_deref().unwrap_or("unset")
+                )));
+            }
+        }
+
+        if !self.profile.is_empty()
+            && !app
+                .server
+                .profile
+                .as_ref()
+                .is_some_and(|p| self.profile.contains(p))
+        {
+            return Ok(Some(format!(
+                "profile {} isn't applied",
+                self.profile.join("/")
+            )));
+        }
+
+        Ok(None)
+    }
+}
+
+impl Downloadable {
+    /// Why this is left out of the server because of its `when`, `None` if it isn't
+    pub fn skip_reason(&self, app: &App) -> Result<Option<String>> {
+        match &self.options().when {
+            Some(condition) => condition
+                .unmet(app)
+                .context(format!("Checking the `when` of {}", self.to_short_string())),
+            None => Ok(None),
+        }
+    }
+}
+
+impl App {
+    /// `list` without the addons whose `when` doesn't match this server
+    pub fn active_addons(&self, list: Vec<Downloadable>) -> Result<Vec<Downloadable>> {
+        let mut active = Vec::with_capacity(list.len());
+
+        for addon in list {
+            if addon.skip_reason(self)?.is_none() {
+                active.push(addon);
+            }
+        }
+
+        Ok(active)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{app::BaseApp, model::ServerType};
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = BaseApp::new()
+            .unwrap()
+            .upgrade_with_default_server()
+            .unwrap();
+        app.server.mc_version = "1.20.4".to_owned();
+        app.server.jar = ServerType::Paper {};
+        app.server.profile = Some("prod".to_owned());
+        app.server
+            .variables
+            .insert("MODE".to_owned(), "survival".to_owned());
+        app
+    }
+
+    #[test]
+    fn matches_when_every_field_matches() {
+        let condition = Condition {
+            loader: vec!["folia".to_owned(), "Paper".to_owned()],
+            mc_version: Some("1.20".to_owned()),
+            variables: BTreeMap::from([("MODE".to_owned(), "survival".to_owned())]),
+            profile: vec!["prod".to_owned()],
+        };
+
+        assert_eq!(condition.unmet(&app()).unwrap(), None);
+    }
+
+    #[test]
+    fn reports_the_unmet_field() {
+        let app = app();
+        let unmet = |condition: Condition| condition.unmet(&app).unwrap();
+
+        assert!(unmet(Condition {
+            loader: vec!["fabric".to_owned()],
+            ..Default::default()
+        })
+        .is_some());
+        assert!(unmet(Condition {
+            mc_version: Some(">=1.21".to_owned()),
+            ..Default::default()
+        })
+        .is_some());
+        assert!(unmet(Condition {
+            variables: BTreeMap::from([("MODE".to_owned(), "creative".to_owned())]),
+            ..Default::default()
+        })
+        .is_some());
+        assert!(unmet(Condition {
+            profile: vec!["dev".to_owned()],
+            ..Default::default()
+        })
+        .is_some());
+        assert!(Condition {
+            mc_version: Some(">=x".to_owned()),
+            ..Default::default()
+        }
+        .unmet(&app)
+        .is_err());
+    }
+}
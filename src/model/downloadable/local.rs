@@ -0,0 +1,132 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use glob::Pattern;
+
+use crate::app::{App, CacheStrategy, ResolvedFile};
+
+use super::Downloadable;
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Files matching the glob `path`, relative to `base` and sorted
+fn glob_files(base: &Path, path: &str) -> Result<Vec<String>> {
+    let pattern = format!(
+        "{}/{path}",
+        Pattern::escape(&base.to_string_lossy()).trim_end_matches('/')
+    );
+
+    let mut files = vec![];
+    for entry in glob::glob(&pattern).context(format!("Invalid glob '{path}'"))? {
+        let entry = entry?;
+        if !entry.is_file() {
+            continue;
+        }
+
+        let relative = entry
+            .strip_prefix(base)
+            .map_err(|_| anyhow!("'{}' isn't inside {}", entry.display(), base.display()))?;
+        files.push(
+            relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        );
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+impl Downloadable {
+    /// Replace every `file` addon with a glob by one `file` addon for each
+    /// file it matches. `base` is the folder paths are relative to
+    pub fn expand_files(list: Vec<Self>, base: &Path) -> Result<Vec<Self>> {
+        let mut expanded = Vec::with_capacity(list.len());
+
+        for addon in list {
+            match addon {
+                Self::File {
+                    path,
+                    filename,
+                    options,
+                } if is_glob(&path) => {
+                    if filename.is_some() {
+                        bail!("'{path}': a file with a glob can't have a filename");
+                    }
+                    if options.hash.is_some() {
+                        bail!("'{path}': a file with a glob can't have a hash");
+                    }
+
+                    let files = glob_files(base, &path)?;
+                    if files.is_empty() {
+                        bail!("'{path}' doesn't match any files");
+                    }
+
+                    expanded.extend(files.into_iter().map(|path| Self::File {
+                        path,
+                        filename: None,
+                        options: options.clone(),
+                    }));
+                }
+                addon => expanded.push(addon),
+            }
+        }
+
+        Ok(expanded)
+    }
+}
+
+impl App {
+    /// Path of a `file` addon relative to server.toml. Globs have to match exactly one file
+    pub fn local_file_path(&self, path: &str) -> Result<String> {
+        if !is_glob(path) {
+            return Ok(path.to_owned());
+        }
+
+        let mut files = glob_files(&self.server.path, path)?;
+        match files.len() {
+            1 => Ok(files.remove(0)),
+            0 => bail!("'{path}' doesn't match any files"),
+            n => bail!("'{path}' matches {n} files, only plugins, mods and datapacks can use globs that match more than one file"),
+        }
+    }
+
+    pub async fn resolve_local_file(
+        &self,
+        path: &str,
+        filename: Option<&String>,
+    ) -> Result<ResolvedFile> {
+        let path = self.local_file_path(path)?;
+        let full_path = self.server.path.join(&path);
+
+        let meta = full_path
+            .metadata()
+            .context(format!("Couldn't find the local file '{path}'"))?;
+        if !meta.is_file() {
+            bail!("'{path}' is not a file");
+        }
+
+        let hash = Self::hash_file(&full_path, "sha256").await?;
+
+        Ok(ResolvedFile {
+            url: path.clone(),
+            filename: match filename {
+                Some(filename) => filename.clone(),
+                None => full_path
+                    .file_name()
+                    .ok_or(anyhow!("'{path}' has no filename"))?
+                    .to_string_lossy()
+                    .into_owned(),
+            },
+            cache: CacheStrategy::Local { path },
+            size: Some(meta.len()),
+            hashes: HashMap::from([("sha256".to_owned(), hash)]),
+            version: None,
+            mirrors: vec![],
+        })
+    }
+}
@@ -1,6 +1,9 @@
 use indexmap::IndexMap;
 
-use crate::{model::Downloadable, sources::jenkins::JenkinsAPI};
+use crate::{
+    model::Downloadable,
+    sources::{curseforge::CurseForgeAPI, gitlab::GitlabAPI, jenkins::JenkinsAPI},
+};
 use std::borrow::Cow;
 
 impl Downloadable {
@@ -18,11 +21,25 @@ impl Downloadable {
             Self::GithubRelease { repo, .. } => {
                 format!("[{repo}](https://github.com/{repo})")
             }
+            Self::Gitlab { url, repo, .. } => {
+                format!("[{repo}]({})", GitlabAPI::get_url(url.as_deref(), repo))
+            }
+            Self::Gitea { url, repo, .. } => {
+                format!("[{repo}]({}/{repo})", url.trim_end_matches('/'))
+            }
+            Self::GithubActions { repo, workflow, .. } => {
+                format!("[{repo}](https://github.com/{repo}/actions/workflows/{workflow})")
+            }
             Self::Hangar { id, .. } => {
                 format!("[{id}](https://hangar.papermc.io/{id})")
             }
-            Self::Jenkins { url, job, .. } => {
-                format!("[{job}]({})", JenkinsAPI::get_url(url, job))
+            Self::Jenkins {
+                url, job, branch, ..
+            } => {
+                format!(
+                    "[{job}]({})",
+                    JenkinsAPI::get_url(url, &JenkinsAPI::branch_job(job, branch.as_deref()))
+                )
             }
             Self::Maven { url, group, .. } => {
                 format!("[{g}]({url}/{g})", g = group.replace('.', "/"))
@@ -36,6 +53,10 @@ impl Downloadable {
             Self::CurseRinth { id, .. } => {
                 format!("`{id}`<sup>[CF](https://www.curseforge.com/minecraft/mc-mods/{id}) [CR](https://curserinth.kuylar.dev/mod/{id})</sup>")
             }
+            Self::CurseForge { id, .. } => {
+                format!("[{id}]({})", CurseForgeAPI::get_url(id))
+            }
+            Self::File { path, .. } => format!("`{path}` (Local file)"),
         }
     }
 
@@ -43,12 +64,17 @@ impl Downloadable {
         match self {
             Self::Url { .. } => "URL",
             Self::GithubRelease { .. } => "GithubRel",
+            Self::GithubActions { .. } => "GithubActions",
+            Self::Gitlab { .. } => "GitLab",
+            Self::Gitea { .. } => "Gitea",
             Self::Jenkins { .. } => "Jenkins",
             Self::Hangar { .. } => "Hangar",
             Self::Modrinth { .. } => "Modrinth",
             Self::CurseRinth { .. } => "CurseRinth",
+            Self::CurseForge { .. } => "CurseForge",
             Self::Spigot { .. } => "Spigot",
             Self::Maven { .. } => "Maven",
+            Self::File { .. } => "File",
         }
         .to_owned()
     }
@@ -65,20 +91,61 @@ impl Downloadable {
                 None,
             ),
 
-            Self::GithubRelease { repo, tag, asset } => {
-                (repo.clone(), Some(asset.clone()), Some(tag.clone()))
-            }
+            Self::File { path, filename, .. } => (path.clone(), filename.clone(), None),
+
+            Self::GithubRelease {
+                repo, tag, asset, ..
+            } => (repo.clone(), Some(asset.clone()), Some(tag.clone())),
+
+            Self::Gitlab {
+                url,
+                repo,
+                tag,
+                asset,
+                ..
+            } => (
+                GitlabAPI::get_url(url.as_deref(), repo),
+                Some(asset.clone()),
+                Some(tag.clone()),
+            ),
+
+            Self::Gitea {
+                url,
+                repo,
+                tag,
+                asset,
+                ..
+            } => (
+                format!("{repo} - ({url})"),
+                Some(asset.clone()),
+                Some(tag.clone()),
+            ),
+
+            Self::GithubActions {
+                repo,
+                workflow,
+                run,
+                artifact,
+                file,
+                ..
+            } => (
+                format!("{repo} - ({workflow})"),
+                Some(format!("{artifact} / {file}")),
+                Some(run.clone()),
+            ),
 
-            Self::Modrinth { id, version }
-            | Self::CurseRinth { id, version }
-            | Self::Hangar { id, version }
-            | Self::Spigot { id, version } => (id.clone(), None, Some(version.clone())),
+            Self::Modrinth { id, version, .. }
+            | Self::CurseRinth { id, version, .. }
+            | Self::CurseForge { id, version, .. }
+            | Self::Hangar { id, version, .. }
+            | Self::Spigot { id, version, .. } => (id.clone(), None, Some(version.clone())),
 
             Self::Jenkins {
                 url,
                 job,
                 build,
                 artifact,
+                ..
             } => (
                 format!("{job} - ({url})"),
                 Some(artifact.clone()),
@@ -91,6 +158,7 @@ impl Downloadable {
                 artifact,
                 version,
                 filename,
+                ..
             } => (
                 format!("{group}.{artifact} - ({url})"),
                 Some(filename.clone()),
@@ -116,8 +184,12 @@ impl Downloadable {
             Self::Modrinth { id, .. } => format!("Modrinth:{id}"),
             Self::Hangar { id, .. } => format!("Hangar:{id}"),
             Self::CurseRinth { id, .. } => format!("CurseRinth:{id}"),
+            Self::CurseForge { id, .. } => format!("CurseForge:{id}"),
             Self::Spigot { id, .. } => format!("Spigot:{id}"),
             Self::GithubRelease { repo, .. } => format!("Github:{repo}"),
+            Self::GithubActions { repo, .. } => format!("GithubActions:{repo}"),
+            Self::Gitlab { repo, .. } => format!("GitLab:{repo}"),
+            Self::Gitea { repo, .. } => format!("Gitea:{repo}"),
             Self::Jenkins { job, .. } => format!("Jenkins:{job}"),
             Self::Url { filename, .. } => {
                 if let Some(f) = filename {
@@ -131,6 +203,7 @@ impl Downloadable {
             } => {
                 format!("Maven:{group}.{artifact}")
             }
+            Self::File { path, .. } => format!("File:{path}"),
         }
     }
 }
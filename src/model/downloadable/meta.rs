@@ -6,9 +6,40 @@ impl Downloadable {
         match (self, other) {
             (Self::Hangar { id: a, .. }, Self::Hangar { id: b, .. }) if a == b => true,
             (Self::CurseRinth { id: a, .. }, Self::CurseRinth { id: b, .. }) if a == b => true,
+            (Self::CurseForge { id: a, .. }, Self::CurseForge { id: b, .. }) if a == b => true,
             (Self::Modrinth { id: a, .. }, Self::Modrinth { id: b, .. }) if a == b => true,
             (Self::Spigot { id: a, .. }, Self::Spigot { id: b, .. }) if a == b => true,
             (Self::Url { url: a, .. }, Self::Url { url: b, .. }) if a == b => true,
+            (
+                Self::Gitlab {
+                    url: ua, repo: a, ..
+                },
+                Self::Gitlab {
+                    url: ub, repo: b, ..
+                },
+            ) if ua == ub && a == b => true,
+            (
+                Self::Gitea {
+                    url: ua, repo: a, ..
+                },
+                Self::Gitea {
+                    url: ub, repo: b, ..
+                },
+            ) if ua == ub && a == b => true,
+            (
+                Self::GithubActions {
+                    repo: a,
+                    workflow: wa,
+                    artifact: aa,
+                    ..
+                },
+                Self::GithubActions {
+                    repo: b,
+                    workflow: wb,
+                    artifact: ab,
+                    ..
+                },
+            ) if a == b && wa == wb && aa == ab => true,
             _ => self == other,
         }
     }
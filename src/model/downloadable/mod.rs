@@ -1,12 +1,25 @@
 use std::collections::HashMap;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::app::{App, CacheStrategy, Resolvable, ResolvedFile};
+use crate::{
+    app::{App, CacheStrategy, Resolvable, ResolvedFile},
+    sources::jenkins::JenkinsAPI,
+};
 
+mod condition;
+mod local;
 mod markdown;
 mod meta;
+mod options;
+mod selector;
+mod update;
+
+pub use condition::Condition;
+pub use options::DownloadOptions;
+pub use selector::*;
+pub use update::AddonUpdate;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Hash, PartialEq, Eq)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -20,6 +33,8 @@ pub enum Downloadable {
         #[serde(default)]
         #[serde(skip_serializing_if = "crate::util::is_default")]
         desc: Option<String>,
+        #[serde(flatten)]
+        options: DownloadOptions,
     },
 
     #[serde(alias = "mr")]
@@ -27,6 +42,14 @@ pub enum Downloadable {
         id: String,
         #[serde(default = "latest")]
         version: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        channel: Option<Channel>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        game_versions: Vec<String>,
+        #[serde(flatten)]
+        options: DownloadOptions,
     },
 
     #[serde(alias = "cr")]
@@ -34,17 +57,44 @@ pub enum Downloadable {
         id: String,
         #[serde(default = "latest")]
         version: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        channel: Option<Channel>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        game_versions: Vec<String>,
+        #[serde(flatten)]
+        options: DownloadOptions,
+    },
+
+    #[serde(alias = "cf")]
+    CurseForge {
+        id: String,
+        #[serde(default = "latest")]
+        version: String,
+        #[serde(flatten)]
+        options: DownloadOptions,
     },
 
     Spigot {
         id: String,
         #[serde(default = "latest")]
         version: String,
+        #[serde(flatten)]
+        options: DownloadOptions,
     },
 
     Hangar {
         id: String,
         version: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        channel: Option<Channel>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        game_versions: Vec<String>,
+        #[serde(flatten)]
+        options: DownloadOptions,
     },
 
     #[serde(rename = "ghrel")]
@@ -52,6 +102,57 @@ pub enum Downloadable {
         repo: String,
         tag: String,
         asset: String,
+        #[serde(flatten)]
+        options: DownloadOptions,
+    },
+
+    /// A release on gitlab.com or a self-hosted GitLab
+    Gitlab {
+        /// The instance, `https://gitlab.com` if not set
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        url: Option<String>,
+        repo: String,
+        #[serde(default = "latest")]
+        tag: String,
+        #[serde(default = "first")]
+        asset: String,
+        #[serde(flatten)]
+        options: DownloadOptions,
+    },
+
+    /// A release on a Gitea or Forgejo instance like codeberg.org
+    #[serde(alias = "forgejo")]
+    Gitea {
+        url: String,
+        repo: String,
+        #[serde(default = "latest")]
+        tag: String,
+        #[serde(default = "first")]
+        asset: String,
+        #[serde(flatten)]
+        options: DownloadOptions,
+    },
+
+    /// An artifact of a GitHub Actions workflow run
+    #[serde(rename = "ghactions")]
+    GithubActions {
+        repo: String,
+        /// Filename of the workflow, like `build.yml`
+        workflow: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        branch: Option<String>,
+        #[serde(default = "success")]
+        status: String,
+        #[serde(default = "latest")]
+        run: String,
+        #[serde(default = "first")]
+        artifact: String,
+        #[serde(default = "first")]
+        file: String,
+        #[serde(flatten)]
+        options: DownloadOptions,
     },
 
     // pain in the a-
@@ -62,6 +163,16 @@ pub enum Downloadable {
         build: String,
         #[serde(default = "first")]
         artifact: String,
+        /// Branch of a multibranch pipeline job
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        branch: Option<String>,
+        /// The worst build result to use, `success`, `unstable`, `failure` or `any`
+        #[serde(default = "success")]
+        #[serde(skip_serializing_if = "is_success")]
+        result: String,
+        #[serde(flatten)]
+        options: DownloadOptions,
     },
 
     Maven {
@@ -70,8 +181,28 @@ pub enum Downloadable {
         artifact: String,
         #[serde(default = "latest")]
         version: String,
-        #[serde(default = "artifact")]
+        #[serde(default = "maven_filename")]
         filename: String,
+        /// Like `all` or `shaded`
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        classifier: Option<String>,
+        /// Extension of the file, `jar` if not set
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        extension: Option<String>,
+        #[serde(flatten)]
+        options: DownloadOptions,
+    },
+
+    /// A file next to server.toml, `path` can be a glob
+    File {
+        path: String,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "crate::util::is_default")]
+        filename: Option<String>,
+        #[serde(flatten)]
+        options: DownloadOptions,
     },
 }
 
@@ -83,12 +214,176 @@ pub fn first() -> String {
     "first".to_owned()
 }
 
-pub fn artifact() -> String {
-    "artifact".to_owned()
+pub fn success() -> String {
+    "success".to_owned()
+}
+
+pub fn is_success(s: &str) -> bool {
+    s == "success"
+}
+
+pub fn maven_filename() -> String {
+    "${artifact}-${version}${classifier}".to_owned()
+}
+
+impl Downloadable {
+    pub fn options(&self) -> &DownloadOptions {
+        match self {
+            Self::Url { options, .. }
+            | Self::Modrinth { options, .. }
+            | Self::CurseRinth { options, .. }
+            | Self::CurseForge { options, .. }
+            | Self::Spigot { options, .. }
+            | Self::Hangar { options, .. }
+            | Self::GithubRelease { options, .. }
+            | Self::Gitlab { options, .. }
+            | Self::Gitea { options, .. }
+            | Self::GithubActions { options, .. }
+            | Self::Jenkins { options, .. }
+            | Self::Maven { options, .. }
+            | Self::File { options, .. } => options,
+        }
+    }
+
+    /// The version, tag, run or build as written in server.toml
+    pub fn version_selector(&self) -> Option<&str> {
+        match self {
+            Self::Modrinth { version, .. }
+            | Self::CurseRinth { version, .. }
+            | Self::CurseForge { version, .. }
+            | Self::Spigot { version, .. }
+            | Self::Hangar { version, .. }
+            | Self::Maven { version, .. } => Some(version),
+            Self::GithubRelease { tag, .. }
+            | Self::Gitlab { tag, .. }
+            | Self::Gitea { tag, .. } => Some(tag),
+            Self::GithubActions { run, .. } => Some(run),
+            Self::Jenkins { build, .. } => Some(build),
+            Self::Url { .. } | Self::File { .. } => None,
+        }
+    }
+
+    /// Whether this can resolve to another file without editing server.toml, like
+    /// `latest`, a range, `${mcver}` or a Maven snapshot
+    pub fn is_floating(&self) -> bool {
+        match self {
+            Self::Url { url, .. } => url.contains('$'),
+            Self::Maven { version, .. } if version.ends_with("-SNAPSHOT") => true,
+            addon => addon.version_selector().is_some_and(|version| {
+                VersionSelector::parse(version).is_floating() || version.contains('$')
+            }),
+        }
+    }
+
+    pub fn options_mut(&mut self) -> &mut DownloadOptions {
+        match self {
+            Self::Url { options, .. }
+            | Self::Modrinth { options, .. }
+            | Self::CurseRinth { options, .. }
+            | Self::CurseForge { options, .. }
+            | Self::Spigot { options, .. }
+            | Self::Hangar { options, .. }
+            | Self::GithubRelease { options, .. }
+            | Self::Gitlab { options, .. }
+            | Self::Gitea { options, .. }
+            | Self::GithubActions { options, .. }
+            | Self::Jenkins { options, .. }
+            | Self::Maven { options, .. }
+            | Self::File { options, .. } => options,
+        }
+    }
+
+    /// The `channel` and `game_versions` of Modrinth, CurseRinth and Hangar addons
+    pub fn version_filter(&self) -> VersionFilter<'_> {
+        match self {
+            Self::Modrinth {
+                channel,
+                game_versions,
+                ..
+            }
+            | Self::CurseRinth {
+                channel,
+                game_versions,
+                ..
+            }
+            | Self::Hangar {
+                channel,
+                game_versions,
+                ..
+            } => VersionFilter {
+                channel: *channel,
+                game_versions,
+            },
+            _ => VersionFilter::default(),
+        }
+    }
 }
 
 impl Resolvable for Downloadable {
     async fn resolve_source(&self, app: &App) -> Result<ResolvedFile> {
+        let fallbacks = &self.options().fallbacks;
+
+        let mut resolved = match self.resolve_own_source(app).await {
+            Ok(resolved) => resolved,
+            Err(err) if !fallbacks.is_empty() => {
+                app.warn(format!(
+                    "Couldn't resolve {}, trying its fallbacks: {err:#}",
+                    self.to_short_string()
+                ));
+                Self::resolve_fallbacks(fallbacks, app).await?
+            }
+            Err(err) => return Err(err),
+        };
+
+        if let Some((algorithm, hash)) = self.options().pinned_hash()? {
+            if let Some(source_hash) = resolved.hashes.get(&algorithm) {
+                if source_hash.to_lowercase() != hash {
+                    bail!(
+                        "{} is pinned to {algorithm}:{hash} but its source has {algorithm}:{source_hash}",
+                        self.to_short_string()
+                    );
+                }
+            }
+
+            resolved.hashes.insert(algorithm, hash);
+        }
+
+        // url fallbacks are the same file, so they can be downloaded from if the source is down,
+        // but only when there's a hash to check that they really are
+        if !resolved.hashes.is_empty() {
+            for fallback in fallbacks {
+                if let Self::Url { url, .. } = fallback {
+                    if *url != resolved.url && !resolved.mirrors.contains(url) {
+                        resolved.mirrors.push(url.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+impl Downloadable {
+    /// The first of `fallbacks` that resolves. Their own fallbacks aren't used
+    async fn resolve_fallbacks(fallbacks: &[Self], app: &App) -> Result<ResolvedFile> {
+        let mut errors = vec![];
+
+        for fallback in fallbacks {
+            match fallback.resolve_own_source(app).await {
+                Ok(resolved) => return Ok(resolved),
+                Err(err) => errors.push(format!("{}: {err:#}", fallback.to_short_string())),
+            }
+        }
+
+        bail!(
+            "None of the fallbacks could be resolved:\n{}",
+            errors.join("\n")
+        )
+    }
+
+    #[allow(clippy::too_many_lines)]
+    async fn resolve_own_source(&self, app: &App) -> Result<ResolvedFile> {
         match self {
             Self::Url { url, filename, .. } => Ok(ResolvedFile {
                 url: url.clone(),
@@ -101,22 +396,88 @@ impl Resolvable for Downloadable {
                 cache: CacheStrategy::None,
                 size: None,
                 hashes: HashMap::new(),
+                version: None,
+                mirrors: vec![],
             }),
-            Self::Modrinth { id, version } => app.modrinth().resolve_source(id, version).await,
-            Self::CurseRinth { id, version } => app.curserinth().resolve_source(id, version).await,
-            Self::Spigot { id, version } => app.spigot().resolve_source(id, version).await,
-            Self::Hangar { id, version } => app.hangar().resolve_source(id, version).await,
-            Self::GithubRelease { repo, tag, asset } => {
-                app.github().resolve_source(repo, tag, asset).await
+            Self::Modrinth { id, version, .. } => {
+                app.modrinth()
+                    .resolve_source(id, version, self.version_filter())
+                    .await
+            }
+            Self::CurseRinth { id, version, .. } => {
+                app.curserinth()
+                    .resolve_source(id, version, self.version_filter())
+                    .await
+            }
+            Self::CurseForge { id, version, .. } => {
+                app.curseforge().resolve_source(id, version).await
+            }
+            Self::Spigot { id, version, .. } => app.spigot().resolve_source(id, version).await,
+            Self::Hangar { id, version, .. } => {
+                app.hangar()
+                    .resolve_source(id, version, self.version_filter())
+                    .await
+            }
+            Self::GithubRelease {
+                repo, tag, asset, ..
+            } => app.github().resolve_source(repo, tag, asset).await,
+            Self::Gitlab {
+                url,
+                repo,
+                tag,
+                asset,
+                ..
+            } => {
+                app.gitlab()
+                    .resolve_source(url.as_deref(), repo, tag, asset)
+                    .await
+            }
+            Self::Gitea {
+                url,
+                repo,
+                tag,
+                asset,
+                ..
+            } => app.gitea().resolve_source(url, repo, tag, asset).await,
+            Self::GithubActions {
+                repo,
+                workflow,
+                branch,
+                status,
+                run,
+                artifact,
+                file,
+                ..
+            } => {
+                app.github()
+                    .resolve_actions_source(
+                        repo,
+                        workflow,
+                        branch.as_deref(),
+                        status,
+                        run,
+                        artifact,
+                        file,
+                    )
+                    .await
             }
             Self::Jenkins {
                 url,
                 job,
                 build,
                 artifact,
+                branch,
+                result,
+                ..
             } => {
                 app.jenkins()
-                    .resolve_source(url, job, build, artifact)
+                    .resolve_source(
+                        url,
+                        &JenkinsAPI::branch_job(job, branch.as_deref()),
+                        build,
+                        artifact,
+                        result,
+                    )
                     .await
             }
             Self::Maven {
@@ -125,11 +486,25 @@ impl Resolvable for Downloadable {
                 artifact,
                 version,
                 filename,
+                classifier,
+                extension,
+                ..
             } => {
                 app.maven()
-                    .resolve_source(url, group, artifact, version, filename)
+                    .resolve_artifact(
+                        url,
+                        group,
+                        artifact,
+                        version,
+                        filename,
+                        classifier.as_deref(),
+                        extension.as_deref(),
+                    )
                     .await
             }
+            Self::File { path, filename, .. } => {
+                app.resolve_local_file(path, filename.as_ref()).await
+            }
         }
     }
 }
@@ -0,0 +1,76 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{Condition, Downloadable};
+
+/// Hashes that can be pinned with `hash`
+pub const PINNABLE_HASHES: [&str; 3] = ["sha512", "sha256", "sha1"];
+
+/// Fields every [`Downloadable`] can have besides the ones of its source
+#[derive(Debug, Deserialize, Serialize, Clone, Hash, PartialEq, Eq, Default)]
+pub struct DownloadOptions {
+    /// Tried in order when this can't be resolved. `url` fallbacks are also
+    /// used as mirrors of the resolved file if it has a hash to check them with
+    #[serde(default)]
+    #[serde(skip_serializing_if = "crate::util::is_default")]
+    pub fallbacks: Vec<Downloadable>,
+    /// Like `sha256:<hash>`, the downloaded file has to match it
+    #[serde(default)]
+    #[serde(skip_serializing_if = "crate::util::is_default")]
+    pub hash: Option<String>,
+    /// Only use this when the server matches
+    #[serde(default)]
+    #[serde(skip_serializing_if = "crate::util::is_default")]
+    pub when: Option<Box<Condition>>,
+}
+
+impl DownloadOptions {
+    /// The pinned `hash` as (algorithm, hash)
+    pub fn pinned_hash(&self) -> Result<Option<(String, String)>> {
+        let Some(pinned) = &self.hash else {
+            return Ok(None);
+        };
+
+        let Some((algorithm, hash)) = pinned.split_once(':') else {
+            bail!("Pinned hash '{pinned}' should look like 'sha256:<hash>'");
+        };
+
+        if !PINNABLE_HASHES.contains(&algorithm) {
+            bail!("Can't pin a '{algorithm}' hash, only sha256, sha512 and sha1");
+        }
+
+        Ok(Some((algorithm.to_owned(), hash.to_lowercase())))
+    }
+
+    /// The options for another version of the addon, the pinned hash is of this one
+    pub fn for_other_version(&self) -> Self {
+        Self {
+            hash: None,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pinned(hash: &str) -> Result<Option<(String, String)>> {
+        DownloadOptions {
+            hash: Some(hash.to_owned()),
+            ..Default::default()
+        }
+        .pinned_hash()
+    }
+
+    #[test]
+    fn parses_pinned_hashes() {
+        assert_eq!(
+            pinned("sha256:ABCDEF").unwrap(),
+            Some(("sha256".to_owned(), "abcdef".to_owned()))
+        );
+        assert_eq!(DownloadOptions::default().pinned_hash().unwrap(), None);
+        assert!(pinned("abcdef").is_err());
+        assert!(pinned("md5:abcdef").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+/// Release channel of a version, from the most to the least stable
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Release,
+    Beta,
+    Alpha,
+}
+
+impl Channel {
+    /// Channel of a version by its name, custom channels count as releases
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+
+        if ["alpha", "snapshot", "dev", "nightly"]
+            .iter()
+            .any(|n| name.contains(n))
+        {
+            Self::Alpha
+        } else if ["beta", "rc", "pre"].iter().any(|n| name.contains(n)) {
+            Self::Beta
+        } else {
+            Self::Release
+        }
+    }
+
+    /// Whether a version on `channel` can be used when this is the least stable channel allowed
+    pub fn allows(self, channel: Self) -> bool {
+        channel <= self
+    }
+}
+
+/// Which versions an addon can use besides its `version`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VersionFilter<'a> {
+    /// The least stable channel allowed, `None` allows everything
+    pub channel: Option<Channel>,
+    /// Game versions to filter by instead of `mc_version`
+    pub game_versions: &'a [String],
+}
+
+pub trait VersionInfo {
+    fn version_number(&self) -> &str;
+    fn channel(&self) -> Channel;
+    /// Whether `version` is this version's id or name
+    fn is_named(&self, version: &str) -> bool;
+}
+
+/// The `version` field of an addon
+#[derive(Debug, Clone)]
+pub enum VersionSelector {
+    /// `latest`, or `latest-release`, `latest-beta` and `latest-alpha` for a channel
+    Latest(Option<Channel>),
+    /// A semver requirement like `^5.2` or `>=1.4, <2`
+    Range(VersionReq),
+    Exact(String),
+}
+
+impl VersionSelector {
+    pub fn parse(version: &str) -> Self {
+        match version {
+            "latest" => Self::Latest(None),
+            "latest-release" => Self::Latest(Some(Channel::Release)),
+            "latest-beta" => Self::Latest(Some(Channel::Beta)),
+            "latest-alpha" => Self::Latest(Some(Channel::Alpha)),
+            version => match version
+                .contains(['^', '~', '<', '>', '=', '*', ','])
+                .then(|| VersionReq::parse(version).ok())
+                .flatten()
+            {
+                Some(req) => Self::Range(req),
+                None => Self::Exact(version.to_owned()),
+            },
+        }
+    }
+
+    /// Whether the concrete version can change without editing server.toml
+    pub fn is_floating(&self) -> bool {
+        !matches!(self, Self::Exact(_))
+    }
+
+    /// The first version in `versions` (newest first) this selects. Exact versions ignore `channel`
+    pub fn select<'v, V: VersionInfo + 'v>(
+        &self,
+        versions: impl IntoIterator<Item = &'v V>,
+        channel: Option<Channel>,
+    ) -> Option<&'v V> {
+        let mut versions = versions.into_iter();
+        let allowed =
+            |v: &&V, channel: Option<Channel>| channel.map_or(true, |c| c.allows(v.channel()));
+
+        match self {
+            Self::Latest(latest) => versions.find(|v| allowed(v, latest.or(channel))),
+            Self::Range(req) => versions.find(|v| {
+                allowed(v, channel)
+                    && parse_version(v.version_number()).is_some_and(|ver| req.matches(&ver))
+            }),
+            Self::Exact(name) => versions.find(|v| v.is_named(name)),
+        }
+    }
+}
+
+/// Read a version number like `v5.2`, `5.2.1+fabric` or `5.3.0-beta.1` as semver,
+/// ignoring pre-release tags since channels are checked separately
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim_start_matches(['v', 'V']);
+    let numeric = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .map_or(version, |end| &version[..end]);
+
+    let mut parts = numeric
+        .split('.')
+        .filter(|p| !p.is_empty())
+        .map(str::parse::<u64>);
+
+    Some(Version::new(
+        parts.next()?.ok()?,
+        parts.next().transpose().ok()?.unwrap_or(0),
+        parts.next().transpose().ok()?.unwrap_or(0),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(parse_version("v5.2"), Some(Version::new(5, 2, 0)));
+        assert_eq!(parse_version("5.2.1+fabric"), Some(Version::new(5, 2, 1)));
+        assert_eq!(parse_version("5.3.0-beta.1"), Some(Version::new(5, 3, 0)));
+        assert_eq!(parse_version("1.20"), Some(Version::new(1, 20, 0)));
+        assert_eq!(parse_version("latest"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn parses_selectors() {
+        assert!(matches!(
+            VersionSelector::parse("latest"),
+            VersionSelector::Latest(None)
+        ));
+        assert!(matches!(
+            VersionSelector::parse("latest-beta"),
+            VersionSelector::Latest(Some(Channel::Beta))
+        ));
+        assert!(matches!(
+            VersionSelector::parse(">=1.4, <2"),
+            VersionSelector::Range(_)
+        ));
+        assert!(matches!(
+            VersionSelector::parse("^5.2"),
+            VersionSelector::Range(_)
+        ));
+        assert!(
+            matches!(VersionSelector::parse("v5.4.102-bukkit"), VersionSelector::Exact(v) if v == "v5.4.102-bukkit")
+        );
+    }
+}
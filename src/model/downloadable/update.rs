@@ -0,0 +1,530 @@
+use anyhow::Result;
+
+use crate::{
+    app::App,
+    sources::{gitlab::GITLAB_URL, jenkins::JenkinsAPI},
+};
+
+use super::{parse_version, Channel, Downloadable, VersionFilter, VersionSelector};
+
+/// A newer version of an addon, from [`Downloadable::check_update`]
+#[derive(Debug, Clone)]
+pub struct AddonUpdate {
+    /// The same addon, pinned to the new version
+    pub addon: Downloadable,
+    pub old_version: String,
+    pub new_version: String,
+    pub changelog: Option<String>,
+}
+
+impl AddonUpdate {
+    /// Whether this only pins `latest` to the version it resolves to right now
+    pub fn is_pin(&self) -> bool {
+        self.old_version == "latest" || self.old_version.starts_with("latest-")
+    }
+}
+
+impl Downloadable {
+    /// Find the newest compatible version of this addon. Returns `None` if it's
+    /// already the newest (or newer, or can't be compared), or if the version can't
+    /// be pinned (urls, `${mcver}`, ranges etc.)
+    #[allow(clippy::too_many_lines)]
+    pub async fn check_update(&self, app: &App) -> Result<Option<AddonUpdate>> {
+        let update = |addon, old_version: &str, new_version: String, changelog| {
+            Some(AddonUpdate {
+                addon,
+                old_version: old_version.to_owned(),
+                new_version,
+                changelog,
+            })
+        };
+
+        Ok(match self {
+            Self::Url { .. } | Self::File { .. } => None,
+
+            Self::Modrinth { version, .. }
+            | Self::CurseRinth { version, .. }
+            | Self::CurseForge { version, .. }
+            | Self::Spigot { version, .. }
+            | Self::Hangar { version, .. }
+            | Self::Maven { version, .. }
+                if version.contains('$') =>
+            {
+                None
+            }
+            Self::Modrinth { version, .. }
+            | Self::CurseRinth { version, .. }
+            | Self::Hangar { version, .. }
+                if matches!(VersionSelector::parse(version), VersionSelector::Range(_)) =>
+            {
+                None
+            }
+            Self::GithubRelease { tag, .. }
+            | Self::Gitlab { tag, .. }
+            | Self::Gitea { tag, .. }
+                if tag.contains('$') =>
+            {
+                None
+            }
+
+            Self::Modrinth {
+                id,
+                version,
+                game_versions,
+                ..
+            } => {
+                let api = app.modrinth();
+                let channel = self.update_channel();
+                let all_versions = api.fetch_all_versions(id).await?;
+                let filtered = api.filter_versions(&all_versions, self.version_filter());
+                let Some(newest) = VersionSelector::Latest(channel)
+                    .select(&filtered, None)
+                    .cloned()
+                else {
+                    return Ok(None);
+                };
+
+                let position = |ver: &str| {
+                    all_versions
+                        .iter()
+                        .position(|v| v.id == ver || v.name == ver || v.version_number == ver)
+                };
+
+                match (VersionSelector::parse(version), position(version)) {
+                    (VersionSelector::Latest(_), _) => {}
+                    (_, Some(current)) if current <= position(&newest.id).unwrap_or_default() => {
+                        return Ok(None)
+                    }
+                    _ => {}
+                }
+
+                let old = all_versions
+                    .iter()
+                    .find(|v| &v.id == version)
+                    .map_or(version.clone(), |v| v.version_number.clone());
+
+                update(
+                    Self::Modrinth {
+                        id: id.clone(),
+                        version: newest.id.clone(),
+                        channel,
+                        game_versions: game_versions.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    &old,
+                    newest.version_number,
+                    Some(newest.changelog),
+                )
+            }
+
+            Self::CurseRinth {
+                id,
+                version,
+                game_versions,
+                ..
+            } => {
+                let channel = self.update_channel();
+                let (versions, unfiltered) = app
+                    .curserinth()
+                    .fetch_versions(id, self.version_filter())
+                    .await?;
+                let Some(newest) = VersionSelector::Latest(channel)
+                    .select(&versions, None)
+                    .cloned()
+                else {
+                    return Ok(None);
+                };
+
+                let ids = unfiltered.iter().map(|v| v.id.as_str()).collect::<Vec<_>>();
+                if !is_latest(version) && !is_newer(&ids, &newest.id, version) {
+                    return Ok(None);
+                }
+
+                let old = unfiltered
+                    .iter()
+                    .find(|v| &v.id == version)
+                    .map_or(version.clone(), |v| v.version_number.clone());
+
+                update(
+                    Self::CurseRinth {
+                        id: id.clone(),
+                        version: newest.id.clone(),
+                        channel,
+                        game_versions: game_versions.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    &old,
+                    newest.version_number,
+                    Some(newest.changelog),
+                )
+            }
+
+            Self::CurseForge { id, version, .. } => {
+                let api = app.curseforge();
+                let Some(newest) = api.fetch_versions(id).await?.into_iter().next() else {
+                    return Ok(None);
+                };
+
+                if !is_latest(version) && !is_newer_build(newest.id, version) {
+                    return Ok(None);
+                }
+
+                let old = if version == "latest" {
+                    version.clone()
+                } else {
+                    api.fetch_file(id, version)
+                        .await
+                        .map_or(version.clone(), |f| f.display_name)
+                };
+
+                update(
+                    Self::CurseForge {
+                        id: id.clone(),
+                        version: newest.id.to_string(),
+                        options: self.options().for_other_version(),
+                    },
+                    &old,
+                    newest.display_name.clone(),
+                    api.fetch_changelog(&newest).await.ok(),
+                )
+            }
+
+            Self::Spigot { id, version, .. } => {
+                let newest = app.spigot().fetch_version(id, "latest").await?;
+
+                if !is_latest(version) && !is_newer_build(newest.id, version) {
+                    return Ok(None);
+                }
+
+                let old = if version == "latest" {
+                    version.clone()
+                } else {
+                    app.spigot()
+                        .fetch_version(id, version)
+                        .await
+                        .map_or(version.clone(), |v| v.name)
+                };
+
+                update(
+                    Self::Spigot {
+                        id: id.clone(),
+                        version: newest.id.to_string(),
+                        options: self.options().for_other_version(),
+                    },
+                    &old,
+                    newest.name,
+                    None,
+                )
+            }
+
+            Self::Hangar {
+                id,
+                version,
+                game_versions,
+                ..
+            } => {
+                let channel = self.update_channel();
+                let api = app.hangar();
+                let newest = api
+                    .fetch_hangar_version(
+                        id,
+                        "latest",
+                        VersionFilter {
+                            channel,
+                            game_versions,
+                        },
+                    )
+                    .await?;
+
+                let versions = mcapi::hangar::fetch_project_versions(
+                    &app.http_client,
+                    id,
+                    Some(api.get_platform_filter()),
+                )
+                .await?
+                .result;
+                let names = versions.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+                if !is_latest(version) && !is_newer(&names, &newest.name, version) {
+                    return Ok(None);
+                }
+
+                update(
+                    Self::Hangar {
+                        id: id.clone(),
+                        version: newest.name.clone(),
+                        channel,
+                        game_versions: game_versions.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    version,
+                    newest.name,
+                    None,
+                )
+            }
+
+            Self::GithubRelease {
+                repo, tag, asset, ..
+            } => {
+                let releases = app.github().fetch_releases(repo).await?;
+                let Some(newest) = releases.first().cloned() else {
+                    return Ok(None);
+                };
+
+                let tags = releases
+                    .iter()
+                    .map(|r| r.tag_name.as_str())
+                    .collect::<Vec<_>>();
+                if !is_latest(tag) && !is_newer(&tags, &newest.tag_name, tag) {
+                    return Ok(None);
+                }
+
+                update(
+                    Self::GithubRelease {
+                        repo: repo.clone(),
+                        tag: newest.tag_name.clone(),
+                        asset: asset.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    tag,
+                    newest.tag_name,
+                    newest.body,
+                )
+            }
+
+            Self::Gitlab {
+                url,
+                repo,
+                tag,
+                asset,
+                ..
+            } => {
+                let releases = app
+                    .gitlab()
+                    .fetch_releases(url.as_deref().unwrap_or(GITLAB_URL), repo)
+                    .await?;
+                let Some(newest) = releases.first().cloned() else {
+                    return Ok(None);
+                };
+
+                let tags = releases
+                    .iter()
+                    .map(|r| r.tag_name.as_str())
+                    .collect::<Vec<_>>();
+                if !is_latest(tag) && !is_newer(&tags, &newest.tag_name, tag) {
+                    return Ok(None);
+                }
+
+                update(
+                    Self::Gitlab {
+                        url: url.clone(),
+                        repo: repo.clone(),
+                        tag: newest.tag_name.clone(),
+                        asset: asset.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    tag,
+                    newest.tag_name,
+                    newest.description,
+                )
+            }
+
+            Self::Gitea {
+                url,
+                repo,
+                tag,
+                asset,
+                ..
+            } => {
+                let releases = app.gitea().fetch_releases(url, repo).await?;
+                let Some(newest) = releases.first().cloned() else {
+                    return Ok(None);
+                };
+
+                let tags = releases
+                    .iter()
+                    .map(|r| r.tag_name.as_str())
+                    .collect::<Vec<_>>();
+                if !is_latest(tag) && !is_newer(&tags, &newest.tag_name, tag) {
+                    return Ok(None);
+                }
+
+                update(
+                    Self::Gitea {
+                        url: url.clone(),
+                        repo: repo.clone(),
+                        tag: newest.tag_name.clone(),
+                        asset: asset.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    tag,
+                    newest.tag_name,
+                    newest.body,
+                )
+            }
+
+            Self::GithubActions {
+                repo,
+                workflow,
+                branch,
+                status,
+                run,
+                artifact,
+                file,
+                ..
+            } => {
+                let api = app.github();
+                let newest = api
+                    .fetch_workflow_run(repo, workflow, branch.as_deref(), status, "latest")
+                    .await?;
+
+                if !is_latest(run) && !is_newer_build(newest.id, run) {
+                    return Ok(None);
+                }
+
+                let old = if run == "latest" {
+                    run.clone()
+                } else {
+                    api.fetch_workflow_run(repo, workflow, branch.as_deref(), status, run)
+                        .await
+                        .map_or(run.clone(), |r| format!("#{}", r.run_number))
+                };
+
+                update(
+                    Self::GithubActions {
+                        repo: repo.clone(),
+                        workflow: workflow.clone(),
+                        branch: branch.clone(),
+                        status: status.clone(),
+                        run: newest.id.to_string(),
+                        artifact: artifact.clone(),
+                        file: file.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    &old,
+                    format!("#{}", newest.run_number),
+                    newest.display_title,
+                )
+            }
+
+            Self::Jenkins {
+                url,
+                job,
+                build,
+                artifact,
+                branch,
+                result,
+                ..
+            } => {
+                let (newest, _) = app
+                    .jenkins()
+                    .fetch_artifact(
+                        url,
+                        &JenkinsAPI::branch_job(job, branch.as_deref()),
+                        "latest",
+                        artifact,
+                        result,
+                    )
+                    .await?;
+
+                if !is_latest(build) && !is_newer_build(newest.number, build) {
+                    return Ok(None);
+                }
+
+                update(
+                    Self::Jenkins {
+                        url: url.clone(),
+                        job: job.clone(),
+                        build: newest.number.to_string(),
+                        artifact: artifact.clone(),
+                        branch: branch.clone(),
+                        result: result.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    build,
+                    newest.number.to_string(),
+                    None,
+                )
+            }
+
+            Self::Maven {
+                url,
+                group,
+                artifact,
+                version,
+                filename,
+                classifier,
+                extension,
+                ..
+            } => {
+                let (newest, versions) = app.maven().fetch_versions(url, group, artifact).await?;
+
+                // maven-metadata.xml lists the oldest version first
+                let versions = versions
+                    .iter()
+                    .rev()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>();
+                if newest.is_empty()
+                    || (!is_latest(version) && !is_newer(&versions, &newest, version))
+                {
+                    return Ok(None);
+                }
+
+                update(
+                    Self::Maven {
+                        url: url.clone(),
+                        group: group.clone(),
+                        artifact: artifact.clone(),
+                        version: newest.clone(),
+                        filename: filename.clone(),
+                        classifier: classifier.clone(),
+                        extension: extension.clone(),
+                        options: self.options().for_other_version(),
+                    },
+                    version,
+                    newest,
+                    None,
+                )
+            }
+        })
+    }
+
+    /// The channel updates are picked from, `latest-beta` etc. keep theirs when pinned
+    fn update_channel(&self) -> Option<Channel> {
+        match self {
+            Self::Modrinth { version, .. }
+            | Self::CurseRinth { version, .. }
+            | Self::Hangar { version, .. } => match VersionSelector::parse(version) {
+                VersionSelector::Latest(Some(channel)) => Some(channel),
+                _ => self.version_filter().channel,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Whether `version` is `latest` (or `latest-beta` etc.), which is pinned to the newest one
+fn is_latest(version: &str) -> bool {
+    matches!(VersionSelector::parse(version), VersionSelector::Latest(_))
+}
+
+/// Whether `newest` is newer than `current`: by their position in `versions` (newest
+/// first) if both are in it, otherwise by their version numbers. `false` if neither works
+fn is_newer(versions: &[&str], newest: &str, current: &str) -> bool {
+    let position = |version| versions.iter().position(|v| *v == version);
+
+    match (position(newest), position(current)) {
+        (Some(newest), Some(current)) => newest < current,
+        _ => match (parse_version(newest), parse_version(current)) {
+            (Some(newest), Some(current)) => newest > current,
+            _ => false,
+        },
+    }
+}
+
+/// Whether the build, file or run id `newest` is newer than `current`
+fn is_newer_build(newest: impl Into<i128>, current: &str) -> bool {
+    current
+        .parse::<i128>()
+        .is_ok_and(|current| newest.into() > current)
+}
@@ -0,0 +1,363 @@
+use std::{
+    collections::BTreeMap,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use super::{Downloadable, Server};
+
+/// Keys that only apply to the file they're written in
+const OWN_KEYS: [&str; 5] = [
+    "extends",
+    "remove_plugins",
+    "remove_mods",
+    "remove_worlds",
+    "remove_hooks",
+];
+
+/// Addon lists, merged entry by entry instead of being replaced
+const ADDON_KEYS: [&str; 2] = ["plugins", "mods"];
+
+/// A server.toml merged with everything it extends
+struct MergedTable {
+    table: toml::Table,
+    /// Which file each value came from
+    origins: BTreeMap<String, PathBuf>,
+}
+
+impl Server {
+    /// Load a server.toml, merging in the files it `extends`
+    pub(super) fn load_extending(path: &Path) -> Result<Self> {
+        let path = path
+            .canonicalize()
+            .context(format!("Couldn't find {}", path.display()))?;
+
+        let data = read_to_string(&path)?;
+        let own: toml::Table = toml::from_str(&data)?;
+
+        let mut serv: Self = if own.contains_key("extends") {
+            let parents = Self::load_parents(&path, &own, &mut vec![path.clone()])?;
+
+            let mut merged = MergedTable {
+                table: parents.table.clone(),
+                origins: parents.origins,
+            };
+            merged.merge(own.clone(), &path)?;
+
+            let mut serv: Self = toml::Value::Table(merged.table).try_into()?;
+            let own_serv: Self = toml::Value::Table(own).try_into()?;
+            serv.extends = own_serv.extends;
+            serv.remove_plugins = own_serv.remove_plugins;
+            serv.remove_mods = own_serv.remove_mods;
+            serv.remove_worlds = own_serv.remove_worlds;
+            serv.remove_hooks = own_serv.remove_hooks;
+
+            // round trip so it compares equal to what saving serializes
+            let inherited: Self = toml::Value::Table(parents.table).try_into()?;
+            serv.inherited = Some(toml::Table::try_from(&inherited)?);
+            serv.origins = merged.origins;
+            serv
+        } else {
+            toml::from_str(&data)?
+        };
+
+        serv.path = path
+            .parent()
+            .ok_or(anyhow!("Couldnt get parent dir"))?
+            .to_path_buf();
+        serv.loaded = Some(serv.to_table()?);
+
+        Ok(serv)
+    }
+
+    /// Load and merge the files `own` extends, in order
+    fn load_parents(
+        path: &Path,
+        own: &toml::Table,
+        chain: &mut Vec<PathBuf>,
+    ) -> Result<MergedTable> {
+        let dir = path.parent().ok_or(anyhow!("Couldnt get parent dir"))?;
+
+        let extends: Vec<PathBuf> = match own.get("extends") {
+            Some(toml::Value::String(s)) => vec![PathBuf::from(s)],
+            Some(value) => value.clone().try_into().context(format!(
+                "{}: 'extends' must be a path or a list of paths",
+                path.display()
+            ))?,
+            None => vec![],
+        };
+
+        let mut merged = MergedTable {
+            table: toml::Table::new(),
+            origins: BTreeMap::new(),
+        };
+
+        for parent in extends {
+            let parent_path = dir.join(&parent).canonicalize().context(format!(
+                "Couldn't find {} (extended by {})",
+                parent.display(),
+                path.display()
+            ))?;
+
+            if chain.contains(&parent_path) {
+                bail!(
+                    "server.toml files extend each other in a loop: {} -> {}",
+                    chain
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(" -> "),
+                    parent_path.display()
+                );
+            }
+
+            let data = read_to_string(&parent_path)
+                .context(format!("Reading {}", parent_path.display()))?;
+            let parent_own: toml::Table =
+                toml::from_str(&data).context(format!("Parsing {}", parent_path.display()))?;
+
+            chain.push(parent_path.clone());
+            let grandparents = Self::load_parents(&parent_path, &parent_own, chain)?;
+            chain.pop();
+
+            merged.merge(grandparents.table, &parent_path)?;
+            merged.origins.extend(grandparents.origins);
+            merged.merge(parent_own, &parent_path)?;
+        }
+
+        Ok(merged)
+    }
+
+    /// The part of this server that isn't inherited, which is what gets saved
+    pub(super) fn own_table(&self, inherited: &toml::Table) -> Result<toml::Table> {
+        let mut serv = self.clone();
+
+        for (key, list, removed) in [
+            ("plugins", &self.plugins, &mut serv.remove_plugins),
+            ("mods", &self.mods, &mut serv.remove_mods),
+        ] {
+            for parent_addon in addons(inherited.get(key)) {
+                let name = parent_addon.to_short_string();
+                if !list.iter().any(|a| a.is_same_as(&parent_addon))
+                    && !removed.iter().any(|r| r.eq_ignore_ascii_case(&name))
+                {
+                    removed.push(name);
+                }
+            }
+        }
+
+        let mut table = toml::Table::try_from(&serv)?;
+
+        for key in ADDON_KEYS {
+            let Some(toml::Value::Array(list)) = table.get_mut(key) else {
+                continue;
+            };
+
+            if let Some(toml::Value::Array(parent_list)) = inherited.get(key) {
+                list.retain(|addon| !parent_list.contains(addon));
+            }
+
+            if list.is_empty() {
+                table.remove(key);
+            }
+        }
+
+        let keys = table.keys().cloned().collect::<Vec<_>>();
+        for key in keys {
+            if OWN_KEYS.contains(&key.as_str()) || ADDON_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+
+            match (table.get_mut(&key), inherited.get(&key)) {
+                (Some(value), Some(parent)) if *value == *parent => {
+                    table.remove(&key);
+                }
+                (Some(toml::Value::Table(value)), Some(toml::Value::Table(parent)))
+                    if key != "jar" =>
+                {
+                    remove_inherited(value, parent);
+                    if value.is_empty() {
+                        table.remove(&key);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(table)
+    }
+}
+
+fn addons(value: Option<&toml::Value>) -> Vec<Downloadable> {
+    value
+        .and_then(|v| v.clone().try_into().ok())
+        .unwrap_or_default()
+}
+
+fn removals(own: &toml::Table, key: &str, file: &Path) -> Result<Vec<String>> {
+    Ok(match own.get(key) {
+        Some(value) => value.clone().try_into().context(format!(
+            "{}: '{key}' must be a list of strings",
+            file.display()
+        ))?,
+        None => vec![],
+    })
+}
+
+/// Remove the values that are the same in `parent` from `table`
+fn remove_inherited(table: &mut toml::Table, parent: &toml::Table) {
+    table.retain(|key, value| match (value, parent.get(key)) {
+        (value, Some(parent)) if *value == *parent => false,
+        (toml::Value::Table(value), Some(toml::Value::Table(parent))) => {
+            remove_inherited(value, parent);
+            !value.is_empty()
+        }
+        _ => true,
+    });
+}
+
+impl MergedTable {
+    /// Merge a file's own table on top:
+    /// - tables are merged key by key, except `jar` which is replaced
+    /// - a plugin or mod replaces the inherited one of the same project, otherwise it's added
+    /// - `remove_plugins`, `remove_mods`, `remove_worlds` and `remove_hooks` remove inherited entries
+    fn merge(&mut self, own: toml::Table, file: &Path) -> Result<()> {
+        let [remove_plugins, remove_mods, remove_worlds, remove_hooks] = [
+            "remove_plugins",
+            "remove_mods",
+            "remove_worlds",
+            "remove_hooks",
+        ]
+        .map(|key| removals(&own, key, file));
+
+        for (key, value) in own {
+            if OWN_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+
+            if ADDON_KEYS.contains(&key.as_str()) {
+                let list: Vec<Downloadable> = value
+                    .clone()
+                    .try_into()
+                    .context(format!("{}: invalid {key}", file.display()))?;
+                let toml::Value::Array(values) = value else {
+                    unreachable!()
+                };
+
+                let mut merged = addons(self.table.get(&key));
+                let mut merged_values = match self.table.remove(&key) {
+                    Some(toml::Value::Array(values)) => values,
+                    _ => vec![],
+                };
+
+                for (addon, value) in list.into_iter().zip(values) {
+                    self.origins.insert(
+                        format!("{key}.{}", addon.to_short_string()),
+                        file.to_path_buf(),
+                    );
+
+                    if let Some(idx) = merged.iter().position(|a| a.is_same_as(&addon)) {
+                        merged[idx] = addon;
+                        merged_values[idx] = value;
+                    } else {
+                        merged.push(addon);
+                        merged_values.push(value);
+                    }
+                }
+
+                self.table.insert(key, toml::Value::Array(merged_values));
+                continue;
+            }
+
+            self.record_origins(&key, &value, file);
+
+            match (self.table.get_mut(&key), value) {
+                (Some(toml::Value::Table(base)), toml::Value::Table(over)) if key != "jar" => {
+                    crate::util::merge_toml(base, over);
+                }
+                (_, value) => {
+                    self.table.insert(key, value);
+                }
+            }
+        }
+
+        self.remove_addons("plugins", "remove_plugins", remove_plugins?, file)?;
+        self.remove_addons("mods", "remove_mods", remove_mods?, file)?;
+        self.remove_entries("worlds", "remove_worlds", remove_worlds?, file)?;
+        self.remove_entries("hooks", "remove_hooks", remove_hooks?, file)?;
+
+        Ok(())
+    }
+
+    fn record_origins(&mut self, key: &str, value: &toml::Value, file: &Path) {
+        match value {
+            toml::Value::Table(table) if key != "jar" => {
+                for (k, v) in table {
+                    self.record_origins(&format!("{key}.{k}"), v, file);
+                }
+            }
+            _ => {
+                self.origins.insert(key.to_owned(), file.to_path_buf());
+            }
+        }
+    }
+
+    fn remove_addons(
+        &mut self,
+        key: &str,
+        own_key: &str,
+        names: Vec<String>,
+        file: &Path,
+    ) -> Result<()> {
+        for name in names {
+            let list = addons(self.table.get(key));
+            let Some(idx) = list
+                .iter()
+                .position(|a| a.to_short_string().eq_ignore_ascii_case(&name))
+            else {
+                bail!(
+                    "{}: {own_key}: '{name}' doesn't match any inherited addon",
+                    file.display()
+                );
+            };
+
+            if let Some(toml::Value::Array(values)) = self.table.get_mut(key) {
+                values.remove(idx);
+            }
+            self.origins
+                .remove(&format!("{key}.{}", list[idx].to_short_string()));
+        }
+
+        Ok(())
+    }
+
+    fn remove_entries(
+        &mut self,
+        key: &str,
+        own_key: &str,
+        names: Vec<String>,
+        file: &Path,
+    ) -> Result<()> {
+        for name in names {
+            let removed = match self.table.get_mut(key) {
+                Some(toml::Value::Table(table)) => table.remove(&name).is_some(),
+                _ => false,
+            };
+
+            if !removed {
+                bail!(
+                    "{}: {own_key}: there's nothing named '{name}' to remove",
+                    file.display()
+                );
+            }
+
+            let prefix = format!("{key}.{name}.");
+            self.origins
+                .retain(|k, _| k != &format!("{key}.{name}") && !k.starts_with(&prefix));
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs::{read_to_string, File},
     io::BufWriter,
     path::{Path, PathBuf},
@@ -9,9 +9,9 @@ use std::{
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use crate::app::ResolvedFile;
+use crate::{app::ResolvedFile, util};
 
-use super::Downloadable;
+use super::{Downloadable, ServerType};
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(default)]
@@ -19,8 +19,17 @@ pub struct Lockfile {
     #[serde(skip)]
     pub path: PathBuf,
 
+    pub server_jar: Option<LockedServerJar>,
+
     pub plugins: Vec<(Downloadable, ResolvedFile)>,
     pub mods: Vec<(Downloadable, ResolvedFile)>,
+    /// Plugins and mods that aren't in server.toml but were added as a
+    /// required dependency of one that is
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<Downloadable>,
+    /// World downloads and datapacks by world name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub worlds: BTreeMap<String, LockedWorld>,
 
     pub server_vars: HashMap<String, String>,
     pub nw_vars: HashMap<String, String>,
@@ -28,10 +37,41 @@ pub struct Lockfile {
     pub files: Vec<BootstrappedFile>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LockedServerJar {
+    pub mc_version: String,
+    #[serde(with = "super::servertype::parse")]
+    pub jar: ServerType,
+    /// The loader version `latest` resolved to, for Fabric and installer based server types
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loader: Option<String>,
+    /// The installer version `latest` resolved to, for Fabric
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installer: Option<String>,
+    /// The installer for installer based server types
+    pub resolved: ResolvedFile,
+    /// The server jar the installer produced, relative to the output directory, and its sha256
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed: Option<(PathBuf, String)>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct LockedWorld {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download: Option<(Downloadable, ResolvedFile)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub datapacks: Vec<(Downloadable, ResolvedFile)>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct BootstrappedFile {
     pub path: PathBuf,
     pub date: SystemTime,
+    /// sha256 of the file in the output, which is the rendered contents for
+    /// files that had variables replaced. Missing in older lockfiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
 }
 
 impl Lockfile {
@@ -54,6 +94,7 @@ impl Lockfile {
     }
 
     pub fn save(&self) -> Result<()> {
+        util::unlink(&self.path)?;
         let writer = BufWriter::new(File::create(&self.path)?);
 
         Ok(serde_json::to_writer_pretty(writer, &self)?)
@@ -64,8 +105,11 @@ impl Default for Lockfile {
     fn default() -> Self {
         Self {
             path: PathBuf::from("./.mcman.lock"),
+            server_jar: None,
             plugins: vec![],
             mods: vec![],
+            dependencies: vec![],
+            worlds: BTreeMap::new(),
             files: vec![],
             server_vars: HashMap::default(),
             nw_vars: HashMap::default(),
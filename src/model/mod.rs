@@ -1,9 +1,11 @@
 mod app_config;
 mod clientsidemod;
 mod downloadable;
+mod extends;
 mod hooks;
 mod lockfile;
 mod network;
+mod profile;
 mod serverlauncher;
 mod servertoml;
 mod servertype;
@@ -15,6 +17,7 @@ pub use downloadable::*;
 pub use hooks::*;
 pub use lockfile::*;
 pub use network::*;
+pub use profile::*;
 pub use serverlauncher::*;
 pub use servertoml::*;
 pub use servertype::*;
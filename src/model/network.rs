@@ -1,14 +1,15 @@
 use std::{
     collections::HashMap,
     env,
-    fs::{read_to_string, File},
-    io::Write,
+    fs::read_to_string,
     path::{Path, PathBuf},
 };
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use crate::util;
+
 use super::{Downloadable, Hook, MarkdownOptions};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -16,6 +17,9 @@ use super::{Downloadable, Hook, MarkdownOptions};
 pub struct Network {
     #[serde(skip)]
     pub path: PathBuf,
+    /// What network.toml was saved as when it was loaded, see [`Network::save`]
+    #[serde(skip)]
+    pub loaded: Option<toml::Table>,
 
     pub name: String,
     pub proxy: String,
@@ -83,15 +87,17 @@ impl Network {
         let data = read_to_string(path)?;
         let mut nw: Self = toml::from_str(&data)?;
         nw.path = path.parent().unwrap().to_path_buf();
+        nw.loaded = Some(toml::Table::try_from(&nw)?);
         Ok(nw)
     }
 
+    /// Save network.toml, only removing the keys that were removed since it was loaded
     pub fn save(&self) -> Result<()> {
-        let cfg_str = toml::to_string_pretty(&self)?;
-        let mut f = File::create(self.path.join("network.toml"))?;
-        f.write_all(cfg_str.as_bytes())?;
-
-        Ok(())
+        util::toml_doc::write(
+            &self.path.join("network.toml"),
+            self.loaded.as_ref(),
+            &toml::Table::try_from(self)?,
+        )
     }
 
     pub fn next_port(&self) -> u16 {
@@ -114,6 +120,7 @@ impl Default for Network {
     fn default() -> Self {
         Self {
             path: PathBuf::from("."),
+            loaded: None,
             name: String::new(),
             proxy: "proxy".to_owned(),
             proxy_groups: vec![],
@@ -0,0 +1,118 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{Downloadable, Server, ServerLauncher, World};
+
+/// Overrides applied on top of server.toml with `--profile <name>`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ServerProfile {
+    /// Output directory, relative to server.toml
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+    /// Same keys as `[launcher]`, only the ones set here are changed
+    #[serde(skip_serializing_if = "toml::Table::is_empty")]
+    pub launcher: toml::Table,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<Downloadable>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mods: Vec<Downloadable>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub worlds: HashMap<String, World>,
+
+    /// Short names (like `Modrinth:spark`) of plugins to leave out
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove_plugins: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove_mods: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove_worlds: Vec<String>,
+}
+
+impl Server {
+    /// Apply the profile `name` to this server. The server can't be saved afterwards
+    pub fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let mut names = self.profiles.keys().cloned().collect::<Vec<_>>();
+            names.sort();
+            bail!(
+                "Unknown profile '{name}', the profiles in server.toml are: {}",
+                if names.is_empty() {
+                    "(none)".to_owned()
+                } else {
+                    names.join(", ")
+                }
+            );
+        };
+
+        self.variables.extend(profile.variables);
+
+        if !profile.launcher.is_empty() {
+            let mut launcher = toml::Table::try_from(&self.launcher)?;
+            crate::util::merge_toml(&mut launcher, profile.launcher);
+            self.launcher = launcher
+                .try_into::<ServerLauncher>()
+                .context(format!("Profile '{name}': invalid launcher settings"))?;
+        }
+
+        remove_addons(&mut self.plugins, &profile.remove_plugins)
+            .context(format!("Profile '{name}': remove_plugins"))?;
+        remove_addons(&mut self.mods, &profile.remove_mods)
+            .context(format!("Profile '{name}': remove_mods"))?;
+        self.plugins.extend(profile.plugins);
+        self.mods.extend(profile.mods);
+
+        for world in &profile.remove_worlds {
+            if self.worlds.remove(world).is_none() {
+                bail!("Profile '{name}': remove_worlds: there's no world named '{world}'");
+            }
+        }
+        self.worlds.extend(profile.worlds);
+
+        self.profile = Some(name.to_owned());
+
+        Ok(())
+    }
+
+    /// The default output directory, `server/` unless the profile sets one
+    pub fn output_dir(&self) -> PathBuf {
+        self.profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+            .and_then(|profile| profile.output.as_ref())
+            .map_or_else(|| self.path.join("server"), |output| self.path.join(output))
+    }
+
+    /// Names of the default output directory and the ones set by profiles
+    pub fn output_dir_names(&self) -> Vec<String> {
+        let mut names = vec!["server".to_owned()];
+
+        for output in self.profiles.values().filter_map(|p| p.output.as_ref()) {
+            let name = output.to_string_lossy().replace('\\', "/");
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+}
+
+fn remove_addons(list: &mut Vec<Downloadable>, remove: &[String]) -> Result<()> {
+    for name in remove {
+        let before = list.len();
+        list.retain(|addon| !addon.to_short_string().eq_ignore_ascii_case(name));
+
+        if list.len() == before {
+            bail!("'{name}' doesn't match any addon");
+        }
+    }
+
+    Ok(())
+}
@@ -1,15 +1,15 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env,
-    fs::{read_to_string, File},
-    io::Write,
     path::{Path, PathBuf},
 };
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use super::{ClientSideMod, Downloadable, Hook, ServerLauncher, ServerType, World};
+use crate::util;
+
+use super::{ClientSideMod, Downloadable, Hook, ServerLauncher, ServerProfile, ServerType, World};
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(default)]
@@ -38,6 +38,22 @@ impl MarkdownOptions {
 pub struct Server {
     #[serde(skip)]
     pub path: PathBuf,
+    /// The profile applied with [`Server::apply_profile`]
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Everything inherited through `extends`, as it would be saved
+    #[serde(skip)]
+    pub inherited: Option<toml::Table>,
+    /// Which file each inherited value came from, see [`Server::load_from`]
+    #[serde(skip)]
+    pub origins: BTreeMap<String, PathBuf>,
+    /// What server.toml was saved as when it was loaded, see [`Server::save`]
+    #[serde(skip)]
+    pub loaded: Option<toml::Table>,
+
+    /// Other server.toml files this one is based on
+    #[serde(skip_serializing_if = "Vec::is_empty", with = "extends_paths")]
+    pub extends: Vec<PathBuf>,
 
     pub name: String,
     pub mc_version: String, // TODO: version type for comparing
@@ -65,6 +81,49 @@ pub struct Server {
     pub mods: Vec<Downloadable>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub clientsidemods: Vec<ClientSideMod>,
+
+    /// Short names of inherited plugins to leave out
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove_plugins: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove_mods: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove_worlds: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove_hooks: Vec<String>,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, ServerProfile>,
+}
+
+/// `extends` can be a single path or a list
+mod extends_paths {
+    use std::path::PathBuf;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(PathBuf),
+        Many(Vec<PathBuf>),
+    }
+
+    pub fn serialize<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+        match paths {
+            [path] => path.serialize(serializer),
+            paths => paths.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<PathBuf>, D::Error> {
+        Ok(match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(path) => vec![path],
+            OneOrMany::Many(paths) => paths,
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
@@ -72,6 +131,18 @@ pub struct ServerOptions {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub bootstrap_exts: Vec<String>,
 
+    /// Don't delete bootstrapped files whose source was removed from `config/`
+    #[serde(default, skip_serializing_if = "crate::util::is_default")]
+    pub keep_stale_files: bool,
+
+    /// Download required Modrinth dependencies that aren't in server.toml when building
+    #[serde(default, skip_serializing_if = "crate::util::is_default")]
+    pub resolve_dependencies: bool,
+
+    /// Fail when an addon in the lockfile is downloaded again but its file changed
+    #[serde(default, skip_serializing_if = "crate::util::is_default")]
+    pub trust_on_first_use: bool,
+
     #[serde(
         default = "default_success_line",
         skip_serializing_if = "is_default_success_line"
@@ -121,22 +192,32 @@ impl Server {
         Self::load_from(&found_path)
     }
 
-    pub fn load_from(path: &PathBuf) -> Result<Self> {
-        let data = read_to_string(path)?;
-        let mut serv: Self = toml::from_str(&data)?;
-        serv.path = path
-            .parent()
-            .ok_or(anyhow!("Couldnt get parent dir"))?
-            .canonicalize()?;
-        Ok(serv)
+    /// Load a server.toml. If it `extends` other files, they're merged in first:
+    /// tables are merged key by key (`jar` is replaced as a whole), plugins and mods
+    /// replace the inherited ones of the same project and are added otherwise
+    pub fn load_from(path: &Path) -> Result<Self> {
+        Self::load_extending(path)
     }
 
+    /// Save server.toml, only removing the keys that were removed since it was loaded
     pub fn save(&self) -> Result<()> {
-        let cfg_str = toml::to_string_pretty(&self)?;
-        let mut f = File::create(self.path.join("server.toml"))?;
-        f.write_all(cfg_str.as_bytes())?;
+        if let Some(profile) = &self.profile {
+            bail!("Can't save server.toml while the profile '{profile}' is applied");
+        }
 
-        Ok(())
+        util::toml_doc::write(
+            &self.path.join("server.toml"),
+            self.loaded.as_ref(),
+            &self.to_table()?,
+        )
+    }
+
+    /// The table saved to server.toml
+    pub(super) fn to_table(&self) -> Result<toml::Table> {
+        Ok(match &self.inherited {
+            Some(inherited) => self.own_table(inherited)?,
+            None => toml::Table::try_from(self)?,
+        })
     }
 
     pub fn format(&self, str: &str) -> String {
@@ -200,6 +281,11 @@ impl Default for Server {
         vars.insert("SERVER_PORT".to_owned(), "25565".to_owned());
         Self {
             path: PathBuf::from("."),
+            profile: None,
+            inherited: None,
+            origins: BTreeMap::new(),
+            loaded: None,
+            extends: vec![],
             name: String::new(),
             mc_version: "latest".to_owned(),
             jar: ServerType::Vanilla {},
@@ -212,6 +298,11 @@ impl Default for Server {
             plugins: vec![],
             mods: vec![],
             clientsidemods: vec![],
+            remove_plugins: vec![],
+            remove_mods: vec![],
+            remove_worlds: vec![],
+            remove_hooks: vec![],
+            profiles: HashMap::new(),
         }
     }
 }
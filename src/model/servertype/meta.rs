@@ -72,7 +72,9 @@ impl ServerType {
                     }
                 }
 
-                Downloadable::GithubRelease { tag, asset, .. } => {
+                Downloadable::GithubRelease { tag, asset, .. }
+                | Downloadable::Gitlab { tag, asset, .. }
+                | Downloadable::Gitea { tag, asset, .. } => {
                     map.insert(Cow::Borrowed("Release"), version_id!(tag));
 
                     if asset != "first" {
@@ -80,6 +82,14 @@ impl ServerType {
                     }
                 }
 
+                Downloadable::GithubActions { run, artifact, .. } => {
+                    map.insert(Cow::Borrowed("Run"), version_id!(run));
+
+                    if artifact != "first" {
+                        map.insert(Cow::Borrowed("Artifact"), format!("`{artifact}`"));
+                    }
+                }
+
                 _ => {}
             },
 
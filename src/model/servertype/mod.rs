@@ -3,7 +3,7 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
-use crate::model::Downloadable;
+use crate::model::{DownloadOptions, Downloadable};
 
 pub mod interactive;
 pub mod meta;
@@ -129,6 +129,27 @@ impl ServerType {
         }
     }
 
+    /// Set the loader version of modded server types, does nothing for others
+    pub fn set_loader(&mut self, version: &str) {
+        match self {
+            Self::Quilt { loader, .. }
+            | Self::Fabric { loader, .. }
+            | Self::NeoForge { loader }
+            | Self::Forge { loader } => version.clone_into(loader),
+            _ => {}
+        }
+    }
+
+    /// Set the installer version of Fabric and Quilt, does nothing for others
+    pub fn set_installer(&mut self, version: &str) {
+        match self {
+            Self::Quilt { installer, .. } | Self::Fabric { installer, .. } => {
+                version.clone_into(installer);
+            }
+            _ => {}
+        }
+    }
+
     pub fn is_modded(&self) -> bool {
         self.get_software_type() == SoftwareType::Modded
     }
@@ -215,6 +236,9 @@ pub fn bungeecord() -> Downloadable {
         job: BUNGEECORD_JOB.to_owned(),
         build: "latest".to_owned(),
         artifact: BUNGEECORD_ARTIFACT.to_owned(),
+        branch: None,
+        result: "success".to_owned(),
+        options: DownloadOptions::default(),
     }
 }
 
@@ -224,5 +248,8 @@ pub fn buildtools() -> Downloadable {
         job: "BuildTools".to_owned(),
         build: "latest".to_owned(),
         artifact: "first".to_owned(),
+        branch: None,
+        result: "success".to_owned(),
+        options: DownloadOptions::default(),
     }
 }
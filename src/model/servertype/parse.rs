@@ -24,7 +24,11 @@ pub fn serialize<S>(st: &ServerType, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    st.serialize(serializer)
+    // written like the downloadable itself, flattening it would write `type` twice
+    match st {
+        ServerType::Downloadable { inner } => inner.serialize(serializer),
+        st => st.serialize(serializer),
+    }
 }
 
 pub fn deserialize<'de, D>(deserializer: D) -> Result<ServerType, D::Error>
@@ -0,0 +1,268 @@
+use std::{borrow::Cow, cmp::Reverse, collections::HashMap};
+
+use anyhow::{anyhow, bail, Context, Result};
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::app::{App, CacheStrategy, ResolvedFile};
+
+/// The `gameId` of Minecraft on curseforge
+pub static MINECRAFT_GAME_ID: &str = "432";
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CurseForgeResponse<T> {
+    pub data: T,
+    pub pagination: Option<CurseForgePagination>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgePagination {
+    pub index: u64,
+    pub page_size: u64,
+    pub result_count: u64,
+    pub total_count: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeMod {
+    pub id: u64,
+    pub name: String,
+    pub slug: String,
+    pub summary: String,
+    pub links: CurseForgeLinks,
+    pub allow_mod_distribution: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeLinks {
+    pub website_url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFile {
+    pub id: u64,
+    pub mod_id: u64,
+    pub display_name: String,
+    pub file_name: String,
+    pub hashes: Vec<CurseForgeHash>,
+    pub file_length: u64,
+    /// `None` if the author disabled third party downloads
+    pub download_url: Option<String>,
+    pub game_versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CurseForgeHash {
+    pub value: String,
+    /// 1 is sha1, 2 is md5
+    pub algo: u8,
+}
+
+impl CurseForgeFile {
+    pub fn hashes(&self) -> HashMap<String, String> {
+        self.hashes
+            .iter()
+            .filter_map(|hash| match hash.algo {
+                1 => Some(("sha1".to_owned(), hash.value.clone())),
+                2 => Some(("md5".to_owned(), hash.value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+pub struct CurseForgeAPI<'a>(pub &'a App);
+
+impl<'a> CurseForgeAPI<'a> {
+    pub async fn fetch_api<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        Ok(self.fetch_response(path, query).await?.data)
+    }
+
+    /// Like [`Self::fetch_api`] but with the pagination of the response
+    pub async fn fetch_response<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<CurseForgeResponse<T>> {
+        let config = &self.0.config.sources.curseforge;
+        let Some(api_key) = &config.api_key else {
+            bail!("CurseForge needs an API key, set CURSEFORGE_API_KEY or sources.curseforge.api_key in .mcman.toml");
+        };
+
+        let response: CurseForgeResponse<T> = self
+            .0
+            .http_client
+            .get(format!(
+                "{}/v1/{path}",
+                config.api_url.trim_end_matches('/')
+            ))
+            .header("x-api-key", api_key)
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Link to the project's page, `id` is the slug or the project id
+    pub fn get_url(id: &str) -> String {
+        if id.parse::<u64>().is_ok() {
+            format!("https://www.curseforge.com/projects/{id}")
+        } else {
+            format!("https://www.curseforge.com/minecraft/mc-mods/{id}")
+        }
+    }
+
+    /// Fetch a project by its slug or id
+    pub async fn fetch_project(&self, id: &str) -> Result<CurseForgeMod> {
+        if id.parse::<u64>().is_ok() {
+            return self.fetch_api(&format!("mods/{id}"), &[]).await;
+        }
+
+        let results: Vec<CurseForgeMod> = self
+            .fetch_api(
+                "mods/search",
+                &[
+                    ("gameId", MINECRAFT_GAME_ID.to_owned()),
+                    ("slug", id.to_owned()),
+                ],
+            )
+            .await?;
+
+        results
+            .into_iter()
+            .find(|project| project.slug == id)
+            .ok_or(anyhow!("CurseForge project '{id}' not found"))
+    }
+
+    pub async fn fetch_project_id(&self, id: &str) -> Result<u64> {
+        match id.parse() {
+            Ok(id) => Ok(id),
+            Err(_) => Ok(self.fetch_project(id).await?.id),
+        }
+    }
+
+    /// `modLoaderType` of the server's loader, `None` for servers without one
+    pub fn get_mod_loader_type(&self) -> Option<u8> {
+        match self.0.server.jar.get_modrinth_name() {
+            Some("forge") => Some(1),
+            Some("fabric") => Some(4),
+            Some("quilt") => Some(5),
+            Some("neoforge") => Some(6),
+            _ => None,
+        }
+    }
+
+    /// Files that are compatible with the server's version and loader, newest first
+    pub async fn fetch_versions(&self, id: &str) -> Result<Vec<CurseForgeFile>> {
+        let project_id = self.fetch_project_id(id).await?;
+
+        let mut query = vec![
+            ("gameVersion", self.0.mc_version().to_owned()),
+            ("pageSize", "50".to_owned()),
+        ];
+        if let Some(loader) = self.get_mod_loader_type() {
+            query.push(("modLoaderType", loader.to_string()));
+        }
+
+        // the api only gives 50 files at a time, so newer ones can be on any page
+        let mut files: Vec<CurseForgeFile> = vec![];
+        loop {
+            let mut page_query = query.clone();
+            page_query.push(("index", files.len().to_string()));
+
+            let response: CurseForgeResponse<Vec<CurseForgeFile>> = self
+                .fetch_response(&format!("mods/{project_id}/files"), &page_query)
+                .await?;
+
+            let page_len = response.data.len();
+            files.extend(response.data);
+
+            let total = response.pagination.map_or(0, |p| p.total_count);
+            if page_len == 0 || files.len() as u64 >= total {
+                break;
+            }
+        }
+        files.sort_by_key(|f| Reverse(f.id));
+
+        Ok(files)
+    }
+
+    pub async fn fetch_file(&self, id: &str, version: &str) -> Result<CurseForgeFile> {
+        match version {
+            "latest" => self
+                .fetch_versions(id)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(anyhow!(
+                    "No compatible files for CurseForge project '{id}' (version 'latest')"
+                )),
+            file_id => {
+                let project_id = self.fetch_project_id(id).await?;
+
+                self.fetch_api(&format!("mods/{project_id}/files/{file_id}"), &[])
+                    .await
+                    .context(format!(
+                        "File '{file_id}' not found for CurseForge project '{id}'"
+                    ))
+            }
+        }
+    }
+
+    /// The changelog of a file as plain text
+    pub async fn fetch_changelog(&self, file: &CurseForgeFile) -> Result<String> {
+        let html: String = self
+            .fetch_api(
+                &format!("mods/{}/files/{}/changelog", file.mod_id, file.id),
+                &[],
+            )
+            .await?;
+
+        let breaks = Regex::new(r"(?i)<br\s*/?>|</p>|</li>")?;
+        let tags = Regex::new(r"<[^>]*>")?;
+
+        Ok(tags
+            .replace_all(&breaks.replace_all(&html, "\n"), "")
+            .replace("&nbsp;", " ")
+            .replace("&amp;", "&")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">"))
+    }
+
+    pub async fn resolve_source(&self, id: &str, version: &str) -> Result<ResolvedFile> {
+        let file = self.fetch_file(id, version).await?;
+
+        let Some(url) = file.download_url.clone() else {
+            bail!(
+                "The author of CurseForge project '{id}' doesn't allow '{}' to be downloaded by other apps, download it manually from {}",
+                file.file_name,
+                Self::get_url(id)
+            );
+        };
+
+        Ok(ResolvedFile {
+            url,
+            filename: file.file_name.clone(),
+            cache: CacheStrategy::File {
+                namespace: Cow::Borrowed("curseforge"),
+                path: format!("{}/{}/{}", file.mod_id, file.id, file.file_name),
+            },
+            size: Some(file.file_length),
+            hashes: file.hashes(),
+            version: None,
+            mirrors: vec![],
+        })
+    }
+}
@@ -2,7 +2,10 @@ use anyhow::{anyhow, Result};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::borrow::Cow;
 
-use crate::app::{App, CacheStrategy, ResolvedFile};
+use crate::{
+    app::{App, CacheStrategy, ResolvedFile},
+    model::{Channel, VersionFilter, VersionInfo, VersionSelector},
+};
 
 use super::modrinth::{ModrinthFile, ModrinthProject, VersionType};
 
@@ -43,6 +46,20 @@ pub struct CurseRinthVersion {
     pub files: Vec<ModrinthFile>,
 }
 
+impl VersionInfo for CurseRinthVersion {
+    fn version_number(&self) -> &str {
+        &self.version_number
+    }
+
+    fn channel(&self) -> Channel {
+        (&self.version_type).into()
+    }
+
+    fn is_named(&self, version: &str) -> bool {
+        self.id == version
+    }
+}
+
 pub static CURSERINTH_API: &str = "https://curserinth-api.kuylar.dev/v2";
 
 pub struct CurserinthAPI<'a>(pub &'a App);
@@ -76,10 +93,12 @@ impl<'a> CurserinthAPI<'a> {
         self.0.server.jar.get_modrinth_name()
     }
 
-    /// Result<(filtered, unfiltered)>
+    /// Result<(filtered, unfiltered)>, filtered by the loader and the game version
+    /// or `filter.game_versions` if it has any
     pub async fn fetch_versions(
         &self,
         id: &str,
+        filter: VersionFilter<'_>,
     ) -> Result<(Vec<CurseRinthVersion>, Vec<CurseRinthVersion>)> {
         let versions = self.fetch_all_versions(id).await?;
 
@@ -93,30 +112,41 @@ impl<'a> CurserinthAPI<'a> {
                         true
                     }
                 })
-                .filter(|v| v.game_versions.contains(&self.0.server.mc_version))
+                .filter(|v| {
+                    if filter.game_versions.is_empty() {
+                        v.game_versions.contains(&self.0.server.mc_version)
+                    } else {
+                        v.game_versions
+                            .iter()
+                            .any(|g| filter.game_versions.contains(g))
+                    }
+                })
                 .cloned()
                 .collect(),
             versions,
         ))
     }
 
-    pub async fn fetch_version(&self, id: &str, version: &str) -> Result<CurseRinthVersion> {
-        let (versions, unfiltered_versions) = self.fetch_versions(id).await?;
-
-        let version = match version {
-            "latest" => {
-                // TODO: unfiltered_versions based on some option
-                versions.first().ok_or(anyhow!(
-                    "No compatible versions for CurseRinth project '{id}' (version 'latest')"
-                ))?
-            }
-            ver => unfiltered_versions
-                .iter()
-                .find(|v| v.id == ver)
-                .ok_or(anyhow!(
-                    "Version '{ver}' not found for CurseRinth project '{id}'"
+    pub async fn fetch_version(
+        &self,
+        id: &str,
+        version: &str,
+        filter: VersionFilter<'_>,
+    ) -> Result<CurseRinthVersion> {
+        let (versions, unfiltered_versions) = self.fetch_versions(id, filter).await?;
+
+        let version =
+            match VersionSelector::parse(version) {
+                VersionSelector::Exact(ver) => unfiltered_versions
+                    .iter()
+                    .find(|v| v.id == ver)
+                    .ok_or(anyhow!(
+                        "Version '{ver}' not found for CurseRinth project '{id}'"
+                    ))?,
+                selector => selector.select(&versions, filter.channel).ok_or(anyhow!(
+                    "No compatible versions for CurseRinth project '{id}' (version '{version}')"
                 ))?,
-        };
+            };
 
         Ok(version.clone())
     }
@@ -125,8 +155,9 @@ impl<'a> CurserinthAPI<'a> {
         &self,
         id: &str,
         version: &str,
+        filter: VersionFilter<'_>,
     ) -> Result<(ModrinthFile, CurseRinthVersion)> {
-        let version = self.fetch_version(id, version).await?;
+        let version = self.fetch_version(id, version, filter).await?;
 
         Ok((
             version
@@ -143,8 +174,13 @@ impl<'a> CurserinthAPI<'a> {
         ))
     }
 
-    pub async fn resolve_source(&self, id: &str, version: &str) -> Result<ResolvedFile> {
-        let (file, version) = self.fetch_file(id, version).await?;
+    pub async fn resolve_source(
+        &self,
+        id: &str,
+        version: &str,
+        filter: VersionFilter<'_>,
+    ) -> Result<ResolvedFile> {
+        let (file, version) = self.fetch_file(id, version, filter).await?;
 
         let cached_file_path = format!("{id}/{}/{}", version.id, file.filename);
 
@@ -157,6 +193,8 @@ impl<'a> CurserinthAPI<'a> {
             },
             size: Some(file.size),
             hashes: file.hashes,
+            version: Some(version.version_number),
+            mirrors: vec![],
         })
     }
 }
@@ -64,6 +64,8 @@ impl<'a> FabricAPI<'a> {
             },
             size: None,
             hashes: HashMap::new(),
+            version: None,
+            mirrors: vec![],
         })
     }
 }
@@ -0,0 +1,197 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    app::{App, CacheStrategy, ResolvedFile},
+    util,
+};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GiteaRelease {
+    pub tag_name: String,
+    pub name: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+    pub assets: Vec<GiteaAsset>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GiteaAsset {
+    pub name: String,
+    pub size: u64,
+    pub browser_download_url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GiteaRepository {
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Works for Forgejo instances too
+pub struct GiteaAPI<'a>(pub &'a App);
+
+impl<'a> GiteaAPI<'a> {
+    /// The token from `sources.gitea`, if it's for the instance at `url`
+    pub fn get_token(&self, url: &str) -> Option<String> {
+        let config = &self.0.config.sources.gitea;
+
+        config.api_token.clone().filter(|_| {
+            config
+                .url
+                .as_ref()
+                .is_some_and(|u| util::is_same_host(u, url))
+        })
+    }
+
+    /// Whether `domain` is a known Gitea or Forgejo instance
+    pub fn is_instance(&self, domain: &str) -> bool {
+        domain == "codeberg.org"
+            || domain.contains("gitea")
+            || domain.contains("forgejo")
+            || self
+                .0
+                .config
+                .sources
+                .gitea
+                .url
+                .as_ref()
+                .is_some_and(|u| util::is_same_host(u, &format!("https://{domain}")))
+    }
+
+    pub async fn fetch_api<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        repo: &str,
+        path: &str,
+    ) -> Result<T> {
+        let mut request = self.0.http_client.get(format!(
+            "{}/api/v1/repos/{repo}{path}",
+            url.trim_end_matches('/')
+        ));
+
+        if let Some(token) = self.get_token(url) {
+            request = request.header("Authorization", format!("token {token}"));
+        }
+
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    /// The newest published releases of the repository (only the first page), newest first
+    pub async fn fetch_releases(&self, url: &str, repo: &str) -> Result<Vec<GiteaRelease>> {
+        Ok(self
+            .fetch_api::<Vec<GiteaRelease>>(url, repo, "/releases")
+            .await?
+            .into_iter()
+            .filter(|r| !r.draft)
+            .collect())
+    }
+
+    /// The release with the tag `release_tag`, or the newest one for `latest`
+    pub async fn fetch_release(
+        &self,
+        url: &str,
+        repo: &str,
+        release_tag: &str,
+    ) -> Result<GiteaRelease> {
+        let tag = release_tag
+            .replace("${mcver}", self.0.mc_version())
+            .replace("${mcversion}", self.0.mc_version());
+
+        if tag == "latest" {
+            return self
+                .fetch_releases(url, repo)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(anyhow!("Repository '{repo}' ({url}) has no Gitea releases"));
+        }
+
+        let not_found = || {
+            format!(
+                "Gitea release '{tag}' ('{release_tag}') not found on repository '{repo}' ({url})"
+            )
+        };
+
+        let release = self
+            .fetch_api::<GiteaRelease>(
+                url,
+                repo,
+                &format!("/releases/tags/{}", util::encode_path_segment(&tag)),
+            )
+            .await
+            .with_context(not_found)?;
+
+        if release.draft {
+            bail!("{}, it's a draft", not_found());
+        }
+
+        Ok(release)
+    }
+
+    pub async fn fetch_asset(
+        &self,
+        url: &str,
+        repo: &str,
+        release_tag: &str,
+        asset_name: &str,
+    ) -> Result<(GiteaRelease, GiteaAsset)> {
+        let release = self.fetch_release(url, repo, release_tag).await?;
+
+        let name = asset_name
+            .replace("${version}", &release.tag_name)
+            .replace("${tag}", &release.tag_name)
+            .replace("${release}", &release.tag_name)
+            .replace("${mcver}", self.0.mc_version())
+            .replace("${mcversion}", self.0.mc_version());
+
+        let asset = util::select_by_name(&release.assets, |a| &a.name, &name)?
+            .cloned()
+            .ok_or(anyhow!(
+                "Gitea release asset '{asset_name}' on release '{}' ('{release_tag}') of repository '{repo}' not found",
+                release.tag_name
+            ))?;
+
+        Ok((release, asset))
+    }
+
+    pub async fn resolve_source(
+        &self,
+        url: &str,
+        repo: &str,
+        release_tag: &str,
+        asset_name: &str,
+    ) -> Result<ResolvedFile> {
+        let (release, asset) = self.fetch_asset(url, repo, release_tag, asset_name).await?;
+
+        Ok(ResolvedFile {
+            url: asset.browser_download_url,
+            cache: CacheStrategy::File {
+                namespace: Cow::Borrowed("gitea"),
+                path: format!(
+                    "{}/{repo}/{}/{}",
+                    util::url_to_folder(url),
+                    release.tag_name,
+                    asset.name
+                ),
+            },
+            filename: asset.name,
+            size: Some(asset.size),
+            hashes: HashMap::new(),
+            version: None,
+            mirrors: vec![],
+        })
+    }
+
+    pub async fn fetch_description(&self, url: &str, repo: &str) -> Result<String> {
+        Ok(self
+            .fetch_api::<GiteaRepository>(url, repo, "")
+            .await?
+            .description
+            .unwrap_or_default())
+    }
+}
@@ -1,18 +1,25 @@
 use std::{
     borrow::Cow,
     collections::HashMap,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use reqwest::{
     header::{HeaderMap, HeaderValue},
     StatusCode,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::time::sleep;
+use zip::ZipArchive;
 
-use crate::app::{App, CacheStrategy, ResolvedFile};
+use crate::{
+    app::{App, CacheStrategy, ResolvedFile},
+    util,
+};
 
 pub trait GithubRequestExt {
     fn with_token(self, token: Option<String>) -> Self;
@@ -64,6 +71,8 @@ pub struct CachedData<T: Serialize> {
 pub struct GithubRelease {
     pub tag_name: String,
     pub name: String,
+    #[serde(default)]
+    pub body: Option<String>,
     pub assets: Vec<GithubAsset>,
 }
 
@@ -79,6 +88,34 @@ pub struct GithubRepository {
     pub description: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GithubWorkflowRuns {
+    pub workflow_runs: Vec<GithubWorkflowRun>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GithubWorkflowRun {
+    pub id: u64,
+    pub run_number: u64,
+    pub head_branch: Option<String>,
+    pub head_sha: String,
+    #[serde(default)]
+    pub display_title: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GithubArtifacts {
+    pub artifacts: Vec<GithubArtifact>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GithubArtifact {
+    pub id: u64,
+    pub name: String,
+    pub archive_download_url: String,
+    pub expired: bool,
+}
+
 static CACHE_DIR: &str = "github";
 static GITHUB_API_VERSION: &str = "2022-11-28";
 
@@ -89,6 +126,16 @@ impl<'a> GithubAPI<'a> {
         &self,
         url: String,
         cache_path: String,
+    ) -> Result<T> {
+        self.fetch_api_query(url, &[], cache_path).await
+    }
+
+    /// [`Self::fetch_api`] with query parameters, which are encoded
+    pub async fn fetch_api_query<T: DeserializeOwned + Clone + Serialize>(
+        &self,
+        url: String,
+        query: &[(&str, &str)],
+        cache_path: String,
     ) -> Result<T> {
         let cached_data = if let Some(cache) = self.0.get_cache(CACHE_DIR) {
             cache.try_get_json::<CachedData<T>>(&cache_path)?
@@ -109,6 +156,7 @@ impl<'a> GithubAPI<'a> {
             .0
             .http_client
             .get(format!("{}/{url}", self.0.config.sources.github.api_url))
+            .query(query)
             .with_token(self.0.config.sources.github.api_token.clone())
             .headers(headers)
             .send()
@@ -240,6 +288,262 @@ impl<'a> GithubAPI<'a> {
             },
             size: Some(asset.size),
             hashes: HashMap::new(),
+            version: None,
+            mirrors: vec![],
+        })
+    }
+
+    /// Runs of a workflow with the status, newest first
+    pub async fn fetch_workflow_runs(
+        &self,
+        repo: &str,
+        workflow: &str,
+        branch: Option<&str>,
+        status: &str,
+    ) -> Result<Vec<GithubWorkflowRun>> {
+        let mut query = vec![("status", status)];
+        if let Some(branch) = branch {
+            query.push(("branch", branch));
+        }
+
+        Ok(self
+            .fetch_api_query::<GithubWorkflowRuns>(
+                format!("repos/{repo}/actions/workflows/{workflow}/runs"),
+                &query,
+                format!(
+                    "{repo}/actions/{workflow}/{}-{status}.json",
+                    branch.map_or("all".to_owned(), util::encode_path_segment)
+                ),
+            )
+            .await?
+            .workflow_runs)
+    }
+
+    /// `run` is `latest` or the id of a run
+    pub async fn fetch_workflow_run(
+        &self,
+        repo: &str,
+        workflow: &str,
+        branch: Option<&str>,
+        status: &str,
+        run: &str,
+    ) -> Result<GithubWorkflowRun> {
+        match run {
+            "latest" => {
+                self.fetch_workflow_runs(repo, workflow, branch, status)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or(anyhow!(
+                    "No '{status}' runs of workflow '{workflow}'{} found on repository '{repo}'",
+                    branch.map(|b| format!(" on branch '{b}'")).unwrap_or_default()
+                ))
+            }
+            id => {
+                self.fetch_api(
+                    format!("repos/{repo}/actions/runs/{id}"),
+                    format!("{repo}/actions/runs/{id}/run.json"),
+                )
+                .await
+            }
+        }
+    }
+
+    pub async fn fetch_artifact(
+        &self,
+        repo: &str,
+        run: &GithubWorkflowRun,
+        artifact_name: &str,
+    ) -> Result<GithubArtifact> {
+        let artifacts = self
+            .fetch_api::<GithubArtifacts>(
+                format!("repos/{repo}/actions/runs/{}/artifacts", run.id),
+                format!("{repo}/actions/runs/{}/artifacts.json", run.id),
+            )
+            .await?
+            .artifacts
+            .into_iter()
+            .filter(|a| !a.expired)
+            .collect::<Vec<_>>();
+
+        let name = artifact_name
+            .replace("${mcver}", self.0.mc_version())
+            .replace("${mcversion}", self.0.mc_version());
+
+        match name.as_str() {
+            "" | "first" | "any" => artifacts.first(),
+            name => artifacts
+                .iter()
+                .find(|a| a.name == name)
+                .or_else(|| artifacts.iter().find(|a| a.name.contains(name))),
+        }
+        .cloned()
+        .ok_or(anyhow!(
+            "Artifact '{artifact_name}' of run #{} on repository '{repo}' not found, it might have expired",
+            run.run_number
+        ))
+    }
+
+    /// Download the artifact zip at `url` and extract the jar matching `file` into `dir`,
+    /// returns its filename and sha256
+    pub async fn extract_artifact(
+        &self,
+        url: &str,
+        file: &str,
+        dir: &Path,
+    ) -> Result<(String, String)> {
+        let Some(token) = self.0.config.sources.github.api_token.clone() else {
+            bail!("Downloading GitHub Actions artifacts needs a GitHub token, set GITHUB_TOKEN");
+        };
+
+        let bytes = self
+            .0
+            .http_client
+            .get(url)
+            .with_token(Some(token))
+            .send()
+            .await?
+            .error_for_status()?
+            .wait_ratelimit()
+            .await?
+            .bytes()
+            .await?;
+
+        let mut archive =
+            ZipArchive::new(Cursor::new(bytes)).context("Opening the artifact zip")?;
+        let names = archive
+            .file_names()
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+
+        let Some(entry) = find_jar(names.iter().map(String::as_str), file)? else {
+            bail!(
+                "No jar matching '{file}' in artifact {url}, it has: {}",
+                names.join(", ")
+            );
+        };
+
+        let mut data = vec![];
+        archive.by_name(entry)?.read_to_end(&mut data)?;
+
+        let filename = entry_name(entry).to_owned();
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(&filename), &data)
+            .context(format!("Extracting {filename} from artifact"))?;
+
+        Ok((filename, hex::encode(Sha256::digest(&data))))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn resolve_actions_source(
+        &self,
+        repo: &str,
+        workflow: &str,
+        branch: Option<&str>,
+        status: &str,
+        run: &str,
+        artifact_name: &str,
+        file: &str,
+    ) -> Result<ResolvedFile> {
+        let run = self
+            .fetch_workflow_run(repo, workflow, branch, status, run)
+            .await?;
+        let artifact = self.fetch_artifact(repo, &run, artifact_name).await?;
+
+        // artifacts are zips, so the jar is extracted into the cache and copied from there
+        let cache_path = format!("{repo}/actions/runs/{}/{}", run.id, artifact.name);
+        let dir = self.artifact_dir(&cache_path);
+
+        let extracted = match std::fs::read_dir(&dir) {
+            Ok(entries) => {
+                let names = entries
+                    .filter_map(|e| Some(e.ok()?.file_name().to_string_lossy().into_owned()))
+                    .collect::<Vec<_>>();
+                find_jar(names.iter().map(String::as_str), file)?.map(ToOwned::to_owned)
+            }
+            Err(_) => None,
+        };
+
+        let (filename, hash) = match extracted {
+            Some(filename) => {
+                let hash = App::hash_file(&dir.join(&filename), "sha256").await?;
+                (filename, hash)
+            }
+            None => {
+                self.extract_artifact(&artifact.archive_download_url, file, &dir)
+                    .await?
+            }
+        };
+
+        let size = dir.join(&filename).metadata()?.len();
+
+        Ok(ResolvedFile {
+            url: artifact.archive_download_url,
+            cache: CacheStrategy::GithubArtifact {
+                path: format!("{cache_path}/{filename}"),
+            },
+            filename,
+            size: Some(size),
+            hashes: HashMap::from([("sha256".to_owned(), hash)]),
+            version: None,
+            mirrors: vec![],
         })
     }
+
+    /// Where artifacts are extracted to, in the cache or a temporary directory
+    pub fn artifact_dir(&self, path: &str) -> PathBuf {
+        match self.0.get_cache(CACHE_DIR) {
+            Some(cache) => cache.path(path),
+            None => std::env::temp_dir()
+                .join("mcman")
+                .join(CACHE_DIR)
+                .join(path),
+        }
+    }
+
+    /// Extract the jar of `resolved` from its artifact again if it isn't there anymore,
+    /// like on a new machine that builds from the lockfile
+    pub async fn ensure_extracted(&self, resolved: &ResolvedFile, path: &str) -> Result<()> {
+        let file_path = self.artifact_dir(path);
+        if file_path.is_file() {
+            return Ok(());
+        }
+
+        let dir = file_path.parent().unwrap_or(&file_path);
+        let (filename, _) = self
+            .extract_artifact(&resolved.url, &resolved.filename, dir)
+            .await?;
+
+        if filename != resolved.filename {
+            bail!(
+                "Artifact {} doesn't have {} anymore",
+                resolved.url,
+                resolved.filename
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// The jar in `names` matching `pattern` (see [`util::select_by_name`]),
+/// sources and javadoc jars are only picked when nothing else matches
+fn find_jar<'n>(names: impl Iterator<Item = &'n str>, pattern: &str) -> Result<Option<&'n str>> {
+    let mut jars = names
+        .filter(|name| {
+            Path::new(entry_name(name))
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("jar"))
+        })
+        .collect::<Vec<_>>();
+    jars.sort_by_key(|name| {
+        entry_name(name).ends_with("-sources.jar") || entry_name(name).ends_with("-javadoc.jar")
+    });
+
+    Ok(util::select_by_name(&jars, |name| entry_name(name), pattern)?.copied())
+}
+
+/// `dir/plugin.jar` => `plugin.jar`
+fn entry_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
 }
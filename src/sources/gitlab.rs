@@ -0,0 +1,198 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    app::{App, CacheStrategy, ResolvedFile},
+    util,
+};
+
+pub static GITLAB_URL: &str = "https://gitlab.com";
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GitlabRelease {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub assets: GitlabReleaseAssets,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GitlabReleaseAssets {
+    pub links: Vec<GitlabAssetLink>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GitlabAssetLink {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub direct_asset_url: Option<String>,
+}
+
+impl GitlabAssetLink {
+    pub fn download_url(&self) -> &str {
+        self.direct_asset_url.as_ref().unwrap_or(&self.url)
+    }
+
+    /// Filename from the download url, links can be named anything
+    pub fn filename(&self) -> &str {
+        self.download_url()
+            .split('?')
+            .next()
+            .and_then(|u| u.rsplit('/').next())
+            .filter(|f| !f.is_empty())
+            .unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GitlabProject {
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+pub struct GitlabAPI<'a>(pub &'a App);
+
+impl<'a> GitlabAPI<'a> {
+    /// The token from `sources.gitlab`, if it's for the instance at `url`
+    pub fn get_token(&self, url: &str) -> Option<String> {
+        let config = &self.0.config.sources.gitlab;
+
+        config
+            .api_token
+            .clone()
+            .filter(|_| util::is_same_host(&config.url, url))
+    }
+
+    /// Link to the project, `repo` is its path like `group/project` or its id
+    pub fn get_url(url: Option<&str>, repo: &str) -> String {
+        format!("{}/{repo}", url.unwrap_or(GITLAB_URL).trim_end_matches('/'))
+    }
+
+    pub async fn fetch_api<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        repo: &str,
+        path: &str,
+    ) -> Result<T> {
+        let mut request = self.0.http_client.get(format!(
+            "{}/api/v4/projects/{}{path}",
+            url.trim_end_matches('/'),
+            repo.replace('/', "%2F")
+        ));
+
+        if let Some(token) = self.get_token(url) {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    /// The newest releases of the project (only the first page), newest first
+    pub async fn fetch_releases(&self, url: &str, repo: &str) -> Result<Vec<GitlabRelease>> {
+        self.fetch_api(url, repo, "/releases").await
+    }
+
+    /// The release with the tag `release_tag`, or the newest one for `latest`
+    pub async fn fetch_release(
+        &self,
+        url: &str,
+        repo: &str,
+        release_tag: &str,
+    ) -> Result<GitlabRelease> {
+        let tag = release_tag
+            .replace("${mcver}", self.0.mc_version())
+            .replace("${mcversion}", self.0.mc_version());
+
+        if tag == "latest" {
+            return self
+                .fetch_releases(url, repo)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(anyhow!("Project '{repo}' ({url}) has no GitLab releases"));
+        }
+
+        self.fetch_api(
+            url,
+            repo,
+            &format!("/releases/{}", util::encode_path_segment(&tag)),
+        )
+        .await
+        .context(format!(
+            "GitLab release '{tag}' ('{release_tag}') not found on project '{repo}' ({url})"
+        ))
+    }
+
+    pub async fn fetch_asset(
+        &self,
+        url: &str,
+        repo: &str,
+        release_tag: &str,
+        asset_name: &str,
+    ) -> Result<(GitlabRelease, GitlabAssetLink)> {
+        let release = self.fetch_release(url, repo, release_tag).await?;
+
+        let name = asset_name
+            .replace("${version}", &release.tag_name)
+            .replace("${tag}", &release.tag_name)
+            .replace("${release}", &release.tag_name)
+            .replace("${mcver}", self.0.mc_version())
+            .replace("${mcversion}", self.0.mc_version());
+
+        let links = &release.assets.links;
+        let asset = match util::select_by_name(links, |a| &a.name, &name)? {
+            Some(asset) => Some(asset),
+            None => util::select_by_name(links, GitlabAssetLink::filename, &name)?,
+        }
+        .cloned()
+            .ok_or(anyhow!(
+                "GitLab release asset '{asset_name}' on release '{}' ('{release_tag}') of project '{repo}' not found",
+                release.tag_name
+            ))?;
+
+        Ok((release, asset))
+    }
+
+    pub async fn resolve_source(
+        &self,
+        url: Option<&str>,
+        repo: &str,
+        release_tag: &str,
+        asset_name: &str,
+    ) -> Result<ResolvedFile> {
+        let url = url.unwrap_or(GITLAB_URL);
+        let (release, asset) = self.fetch_asset(url, repo, release_tag, asset_name).await?;
+
+        let filename = asset.filename().to_owned();
+
+        Ok(ResolvedFile {
+            cache: CacheStrategy::File {
+                namespace: Cow::Borrowed("gitlab"),
+                path: format!(
+                    "{}/{repo}/{}/{filename}",
+                    util::url_to_folder(url),
+                    release.tag_name
+                ),
+            },
+            url: asset.download_url().to_owned(),
+            filename,
+            size: None,
+            hashes: HashMap::new(),
+            version: None,
+            mirrors: vec![],
+        })
+    }
+
+    pub async fn fetch_description(&self, url: Option<&str>, repo: &str) -> Result<String> {
+        Ok(self
+            .fetch_api::<GitlabProject>(url.unwrap_or(GITLAB_URL), repo, "")
+            .await?
+            .description
+            .unwrap_or_default())
+    }
+}
@@ -1,19 +1,34 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, path::Path};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use mcapi::hangar::{Platform, ProjectVersion};
 
 use crate::{
     app::{App, CacheStrategy, ResolvedFile},
-    model::ServerType,
+    model::{Channel, Downloadable, ServerType, VersionFilter, VersionInfo, VersionSelector},
 };
 
+impl VersionInfo for ProjectVersion {
+    fn version_number(&self) -> &str {
+        &self.name
+    }
+
+    fn channel(&self) -> Channel {
+        Channel::from_name(&self.channel.name)
+    }
+
+    fn is_named(&self, version: &str) -> bool {
+        self.name == version
+    }
+}
+
 pub async fn get_project_version(
     http_client: &reqwest::Client,
     id: &str,
     filter: Option<mcapi::hangar::PlatformFilter>,
-    platform_version: Option<String>,
-    plugin_version: Option<&str>,
+    platform_versions: &[String],
+    selector: &VersionSelector,
+    channel: Option<Channel>,
 ) -> Result<mcapi::hangar::ProjectVersion> {
     // Use the provided filter or create a default one.
     let mut current_filter = filter.unwrap_or_default();
@@ -21,27 +36,26 @@ pub async fn get_project_version(
     // Closure to search for a version in a page.
     let find_version =
         |versions: &[mcapi::hangar::ProjectVersion]| -> Option<mcapi::hangar::ProjectVersion> {
-            let mut compatible_versions = versions.iter().filter(|v| {
-                if let (Some(platform), Some(platform_version)) =
-                    (&current_filter.platform, &platform_version)
-                {
-                    v.platform_dependencies
-                        .get(&platform)
-                        .unwrap()
-                        .contains(&platform_version)
+            let compatible_versions = versions.iter().filter(|v| {
+                if let Some(platform) = &current_filter.platform {
+                    platform_versions.is_empty()
+                        || v.platform_dependencies
+                            .get(platform)
+                            .is_some_and(|deps| platform_versions.iter().any(|p| deps.contains(p)))
                 } else {
                     true
                 }
             });
 
-            if let Some(plugin_version) = plugin_version {
-                compatible_versions
-                    .find(|v| v.name == plugin_version)
-                    .or_else(|| versions.iter().find(|v| v.name.contains(plugin_version)))
-                    .cloned()
-            } else {
-                compatible_versions.next().cloned()
-            }
+            selector
+                .select(compatible_versions, channel)
+                .or_else(|| match selector {
+                    VersionSelector::Exact(plugin_version) => {
+                        versions.iter().find(|v| v.name.contains(plugin_version))
+                    }
+                    _ => None,
+                })
+                .cloned()
         };
 
     loop {
@@ -65,7 +79,7 @@ pub async fn get_project_version(
     }
 
     // Return a detailed error if no version was found.
-    if let Some(plugin_version) = plugin_version {
+    if let VersionSelector::Exact(plugin_version) = selector {
         Err(anyhow!(
             "No compatible versions ('{}') for Hangar project '{}'",
             plugin_version,
@@ -79,37 +93,71 @@ pub async fn get_project_version(
     }
 }
 
+/// Whether `addon` is the plugin called `name`, by its Hangar id or the name of
+/// its file without the version
+fn is_plugin(addon: &Downloadable, resolved: &ResolvedFile, name: &str) -> bool {
+    let normalize = |s: &str| {
+        s.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let name = normalize(name);
+
+    if let Downloadable::Hangar { id, .. } = addon {
+        if normalize(id.rsplit('/').next().unwrap_or(id)) == name {
+            return true;
+        }
+    }
+
+    // ProtocolLib-5.1.0.jar => ProtocolLib, LuckPerms-Bukkit-5.4.jar => LuckPerms(-Bukkit)
+    let stem = Path::new(&resolved.filename)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let parts = stem
+        .split(['-', '_', ' ', '+'])
+        .take_while(|part| {
+            !part
+                .trim_start_matches(['v', 'V'])
+                .starts_with(|c: char| c.is_ascii_digit())
+        })
+        .collect::<Vec<_>>();
+
+    parts.first().is_some_and(|first| normalize(first) == name)
+        || normalize(&parts.concat()) == name
+}
+
 pub struct HangarAPI<'a>(pub &'a App);
 
 impl<'a> HangarAPI<'a> {
-    pub async fn fetch_hangar_version(&self, id: &str, version: &str) -> Result<ProjectVersion> {
+    pub async fn fetch_hangar_version(
+        &self,
+        id: &str,
+        version: &str,
+        version_filter: VersionFilter<'_>,
+    ) -> Result<ProjectVersion> {
         let filter = self.get_platform_filter();
-        let platform_version = if filter.platform.is_some() {
-            Some(self.0.mc_version().to_owned())
+        let platform_versions = if version_filter.game_versions.is_empty() {
+            vec![self.0.mc_version().to_owned()]
         } else {
-            None
+            version_filter.game_versions.to_vec()
         };
 
-        let version = if version == "latest" {
-            get_project_version(
-                &self.0.http_client,
-                id,
-                Some(filter),
-                platform_version,
-                None,
-            )
-            .await?
-        } else if version.contains('$') {
-            let version = version
+        let selector = VersionSelector::parse(
+            &version
                 .replace("${mcver}", self.0.mc_version())
-                .replace("${mcversion}", self.0.mc_version());
+                .replace("${mcversion}", self.0.mc_version()),
+        );
 
+        let version = if selector.is_floating() || version.contains('$') {
             get_project_version(
                 &self.0.http_client,
                 id,
                 Some(filter),
-                platform_version,
-                Some(&version),
+                &platform_versions,
+                &selector,
+                version_filter.channel,
             )
             .await?
         } else {
@@ -147,13 +195,81 @@ impl<'a> HangarAPI<'a> {
         }
     }
 
+    /// Error if `version` doesn't support the server's game version (or `game_versions`)
+    /// on its platform
+    pub fn check_version(
+        &self,
+        id: &str,
+        version: &ProjectVersion,
+        filter: VersionFilter<'_>,
+    ) -> Result<()> {
+        let platform = self.get_platform().unwrap_or(Platform::Paper);
+
+        if let Some(supported) = version.platform_dependencies.get(&platform) {
+            let mc_version = self.0.mc_version().to_owned();
+            let game_versions = if filter.game_versions.is_empty() {
+                std::slice::from_ref(&mc_version)
+            } else {
+                filter.game_versions
+            };
+
+            if !game_versions.iter().any(|v| supported.contains(v)) {
+                bail!(
+                    "Hangar project '{id}' version '{}' doesn't support {platform:?} {}, it supports: {}",
+                    version.name,
+                    game_versions.join(", "),
+                    supported.join(", ")
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Required plugins of the Hangar project `id` at `version` that aren't in `plugins`,
+    /// with their url if they aren't on Hangar
+    pub async fn missing_dependencies(
+        &self,
+        id: &str,
+        version: &str,
+        plugins: &[(Downloadable, ResolvedFile)],
+    ) -> Result<Vec<String>> {
+        let version =
+            mcapi::hangar::fetch_project_version(&self.0.http_client, id, version).await?;
+        let platform = self.get_platform().unwrap_or(Platform::Paper);
+
+        Ok(version
+            .plugin_dependencies
+            .get(&platform)
+            .into_iter()
+            .flatten()
+            .filter(|dep| dep.required)
+            .filter(|dep| {
+                !plugins
+                    .iter()
+                    .any(|(addon, resolved)| is_plugin(addon, resolved, &dep.name))
+            })
+            .map(|dep| match &dep.external_url {
+                Some(url) => format!("{} ({url})", dep.name),
+                None => dep.name.clone(),
+            })
+            .collect())
+    }
+
     #[allow(clippy::cast_sign_loss)]
-    pub async fn resolve_source(&self, id: &str, version: &str) -> Result<ResolvedFile> {
+    pub async fn resolve_source(
+        &self,
+        id: &str,
+        version: &str,
+        filter: VersionFilter<'_>,
+    ) -> Result<ResolvedFile> {
         let version = self
-            .fetch_hangar_version(id, version)
+            .fetch_hangar_version(id, version, filter)
             .await
             .context("Fetching project version")?;
 
+        self.check_version(id, &version, filter)?;
+
         let download = version
             .downloads
             .get(&self.get_platform().unwrap_or(Platform::Paper))
@@ -173,6 +289,61 @@ impl<'a> HangarAPI<'a> {
             },
             size: Some(download.get_file_info().size_bytes as u64),
             hashes: HashMap::from([("sha256".to_owned(), download.get_file_info().sha256_hash)]),
+            version: Some(version.name.clone()),
+            mirrors: vec![],
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::model::DownloadOptions;
+
+    use super::*;
+
+    fn is_plugin_file(addon: &Downloadable, filename: &str, name: &str) -> bool {
+        let resolved = ResolvedFile {
+            url: String::new(),
+            filename: filename.to_owned(),
+            cache: CacheStrategy::None,
+            size: None,
+            hashes: HashMap::new(),
+            version: None,
+            mirrors: vec![],
+        };
+
+        is_plugin(addon, &resolved, name)
+    }
+
+    #[test]
+    fn matches_plugins_by_slug_or_filename() {
+        let url = Downloadable::Url {
+            url: String::new(),
+            filename: None,
+            desc: None,
+            options: DownloadOptions::default(),
+        };
+        let hangar = Downloadable::Hangar {
+            id: "Owner/Vault".to_owned(),
+            version: "latest".to_owned(),
+            channel: None,
+            game_versions: vec![],
+            options: DownloadOptions::default(),
+        };
+
+        assert!(is_plugin_file(&hangar, "VaultUnlocked.jar", "Vault"));
+        assert!(is_plugin_file(&url, "ProtocolLib-5.1.0.jar", "ProtocolLib"));
+        assert!(is_plugin_file(
+            &url,
+            "LuckPerms-Bukkit-5.4.102.jar",
+            "LuckPerms"
+        ));
+        assert!(is_plugin_file(&url, "worldedit_v7.3.jar", "WorldEdit"));
+        assert!(!is_plugin_file(&url, "VaultUnlocked-2.0.jar", "Vault"));
+        assert!(!is_plugin_file(
+            &url,
+            "ProtocolLibExtras-1.0.jar",
+            "ProtocolLib"
+        ));
+    }
+}
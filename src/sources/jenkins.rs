@@ -1,18 +1,26 @@
 use std::{borrow::Cow, collections::HashMap};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::app::{App, CacheStrategy, ResolvedFile};
+use crate::{
+    app::{App, CacheStrategy, ResolvedFile},
+    util,
+};
 
-static SUCCESS_STR: &str = "SUCCESS";
+/// Build results from the best to the worst
+static RESULTS: [&str; 5] = ["SUCCESS", "UNSTABLE", "FAILURE", "NOT_BUILT", "ABORTED"];
+
+/// How many builds `latest` looks through for one with a matching artifact
+static LATEST_BUILD_TRIES: usize = 10;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct JenkinsBuildItem {
     pub url: String,
     pub number: i32,
-    pub result: String,
+    /// `None` while the build is running
+    pub result: Option<String>,
     #[serde(default)]
     pub fingerprint: Vec<JenkinsFingerprint>,
 }
@@ -29,13 +37,54 @@ pub struct JenkinsArtifact {
 pub struct JenkinsFingerprint {
     #[serde(default)]
     file_name: String,
+    /// md5 of the file
     #[serde(default)]
     hash: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JenkinsBuildDetails {
+    #[serde(default)]
+    pub artifacts: Vec<JenkinsArtifact>,
+    #[serde(default)]
+    pub fingerprint: Vec<JenkinsFingerprint>,
+}
+
+/// Whether a build that ended with `result` can be used when `wanted` is the worst
+/// result allowed, `any` allows every finished build
+pub fn is_result_allowed(wanted: &str, result: Option<&str>) -> Result<bool> {
+    let Some(result) = result else {
+        return Ok(false);
+    };
+
+    if wanted.eq_ignore_ascii_case("any") {
+        return Ok(true);
+    }
+
+    let rank = |r: &str| RESULTS.iter().position(|x| x.eq_ignore_ascii_case(r));
+    let Some(wanted_rank) = rank(wanted) else {
+        bail!(
+            "Unknown Jenkins build result '{wanted}', use one of: any, {}",
+            RESULTS.join(", ").to_lowercase()
+        );
+    };
+
+    Ok(rank(result).is_some_and(|r| r <= wanted_rank))
+}
+
 pub struct JenkinsAPI<'a>(pub &'a App);
 
 impl<'a> JenkinsAPI<'a> {
+    /// The job of `branch` in a multibranch pipeline job
+    pub fn branch_job(job: &str, branch: Option<&str>) -> String {
+        match branch {
+            // jenkins encodes the slashes of branch names twice
+            Some(branch) => format!("{job}/{}", branch.replace('/', "%252F")),
+            None => job.to_owned(),
+        }
+    }
+
     pub fn get_url(url: &str, job: &str) -> String {
         job.split('/')
             .fold(url.strip_suffix('/').unwrap_or(url).to_owned(), |acc, j| {
@@ -60,78 +109,117 @@ impl<'a> JenkinsAPI<'a> {
         )?)
     }
 
-    pub async fn fetch_build(&self, url: &str, job: &str, build: &str) -> Result<JenkinsBuildItem> {
-        let builds = self
+    /// Finished builds with a result at least as good as `result`, newest first
+    pub async fn fetch_builds_with_result(
+        &self,
+        url: &str,
+        job: &str,
+        result: &str,
+    ) -> Result<Vec<JenkinsBuildItem>> {
+        let mut builds = vec![];
+        for build in self
             .fetch_builds(url, job)
             .await
-            .context("Fetching jenkins builds")?;
-        let builds = builds
-            .into_iter()
-            .filter(|b| b.result == SUCCESS_STR)
-            .collect::<Vec<_>>();
+            .context("Fetching jenkins builds")?
+        {
+            if is_result_allowed(result, build.result.as_deref())? {
+                builds.push(build);
+            }
+        }
+
+        Ok(builds)
+    }
+
+    pub async fn fetch_build(
+        &self,
+        url: &str,
+        job: &str,
+        build: &str,
+        result: &str,
+    ) -> Result<JenkinsBuildItem> {
+        let builds = self.fetch_builds_with_result(url, job, result).await?;
 
         let selected_build = match build {
             "latest" => builds.first(),
             id => builds.iter().find(|b| b.number.to_string() == id),
         }
         .ok_or(anyhow!(
-            "Can't find Jenkins build '{build}', URL: '{url}', Job: '{job}'"
+            "Can't find Jenkins build '{build}' with a result of {result} or better, URL: '{url}', Job: '{job}'"
         ))?
         .clone();
 
         Ok(selected_build)
     }
 
-    pub async fn fetch_artifacts(&self, build_url: &str) -> Result<Vec<JenkinsArtifact>> {
-        Ok(serde_json::from_value(
-            self.0
-                .http_client
-                .get(format!("{build_url}/api/json?tree=artifacts[*]"))
-                .send()
-                .await?
-                .error_for_status()?
-                .json::<serde_json::Value>()
-                .await?["artifacts"]
-                .take(),
-        )?)
+    /// Artifacts and fingerprints of a build
+    pub async fn fetch_build_details(&self, build_url: &str) -> Result<JenkinsBuildDetails> {
+        Ok(self
+            .0
+            .http_client
+            .get(format!(
+                "{build_url}/api/json?tree=artifacts[*],fingerprint[fileName,hash]"
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
     }
 
+    /// The build and its artifact named `artifact` (see [`util::select_by_name`]).
+    /// `latest` is the newest build with a matching artifact, not just the newest build
     pub async fn fetch_artifact(
         &self,
         url: &str,
         job: &str,
         build: &str,
         artifact: &str,
+        result: &str,
     ) -> Result<(JenkinsBuildItem, JenkinsArtifact)> {
         let job = &job
             .replace("${mcver}", self.0.mc_version())
             .replace("${mcversion}", self.0.mc_version());
 
-        let selected_build = self
-            .fetch_build(url, job, build)
-            .await
-            .context("Fetching jenkins build")?;
+        let builds = match build {
+            "latest" => {
+                let mut builds = self.fetch_builds_with_result(url, job, result).await?;
+                builds.truncate(LATEST_BUILD_TRIES);
+                builds
+            }
+            build => vec![self
+                .fetch_build(url, job, build, result)
+                .await
+                .context("Fetching jenkins build")?],
+        };
 
-        let artifacts = self
-            .fetch_artifacts(&selected_build.url)
-            .await
-            .context("Fetching jenkins artifacts")?;
+        for mut selected_build in builds {
+            let details = self
+                .fetch_build_details(&selected_build.url)
+                .await
+                .context("Fetching jenkins artifacts")?;
 
-        let artifact = artifact
-            .replace("${mcver}", self.0.mc_version())
-            .replace("${mcversion}", self.0.mc_version())
-            .replace("${build}", &selected_build.number.to_string());
-
-        let selected_artifact = match artifact.as_str() {
-            "first" => artifacts.first(),
-            id => artifacts.iter().find(|a| a.file_name == id)
-                .or_else(|| artifacts.iter().find(|a| a.file_name.contains(id))),
-        }.ok_or(anyhow!(
-            "Can't find Jenkins artifact '{artifact}', on build '{}' ({build}), job '{job}', url: '{url}'",
-            selected_build.number
-        ))?.clone();
-
-        Ok((selected_build, selected_artifact))
+            let pattern = artifact
+                .replace("${mcver}", self.0.mc_version())
+                .replace("${mcversion}", self.0.mc_version())
+                .replace("${build}", &selected_build.number.to_string());
+
+            if let Some(selected_artifact) =
+                util::select_by_name(&details.artifacts, |a| &a.file_name, &pattern)?
+            {
+                let selected_artifact = selected_artifact.clone();
+                selected_build.fingerprint = details.fingerprint;
+                return Ok((selected_build, selected_artifact));
+            }
+
+            if build != "latest" {
+                bail!(
+                    "Can't find Jenkins artifact '{pattern}' ('{artifact}'), on build '{}', job '{job}', url: '{url}'",
+                    selected_build.number
+                );
+            }
+        }
+
+        bail!("None of the last {LATEST_BUILD_TRIES} Jenkins builds with a result of {result} or better have an artifact matching '{artifact}', job '{job}', url: '{url}'")
     }
 
     pub async fn resolve_source(
@@ -140,9 +228,10 @@ impl<'a> JenkinsAPI<'a> {
         job: &str,
         build: &str,
         artifact: &str,
+        result: &str,
     ) -> Result<ResolvedFile> {
         let (build, artifact) = self
-            .fetch_artifact(url, job, build, artifact)
+            .fetch_artifact(url, job, build, artifact, result)
             .await
             .context("Fetching jenkins artifact")?;
 
@@ -170,6 +259,8 @@ impl<'a> JenkinsAPI<'a> {
             } else {
                 HashMap::new()
             },
+            version: None,
+            mirrors: vec![],
         })
     }
 
@@ -192,3 +283,19 @@ impl<'a> JenkinsAPI<'a> {
             .unwrap_or_default())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_results_up_to_wanted() {
+        assert!(is_result_allowed("success", Some("SUCCESS")).unwrap());
+        assert!(!is_result_allowed("success", Some("UNSTABLE")).unwrap());
+        assert!(is_result_allowed("unstable", Some("SUCCESS")).unwrap());
+        assert!(is_result_allowed("any", Some("ABORTED")).unwrap());
+        // still running
+        assert!(!is_result_allowed("any", None).unwrap());
+        assert!(is_result_allowed("finished", Some("SUCCESS")).is_err());
+    }
+}
@@ -1,8 +1,17 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use reqwest::StatusCode;
 
-use crate::app::{App, CacheStrategy, ResolvedFile};
+use crate::{
+    app::{App, CacheStrategy, ResolvedFile},
+    util,
+};
 
 pub trait XMLExt {
     fn get_text(&self, k: &str) -> Result<String>;
@@ -54,9 +63,200 @@ impl MavenMetadata {
     }
 }
 
+/// Whether `file` already ends with an extension like `.jar`, versions like `1.2`
+/// or `1.0-SNAPSHOT` don't count
+fn has_extension(file: &str) -> bool {
+    file.rsplit_once('.').is_some_and(|(_, ext)| {
+        ext.starts_with(|c: char| c.is_ascii_alphabetic())
+            && ext.chars().all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+/// Whether `err` is because the file doesn't exist, locally or on the repository
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.chain().any(|e| {
+        e.downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == ErrorKind::NotFound)
+            || e.downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.status() == Some(StatusCode::NOT_FOUND))
+    })
+}
+
+/// Whether `url` is inside the repository `repo`: same scheme, host and port, and
+/// a path under the repository's, so `https://repo.example.com/maven` doesn't match
+/// `https://repo.example.com/maven-evil` or `https://repo.example.com.evil.net/maven`
+fn is_in_repo(url: &str, repo: &str) -> bool {
+    let (Ok(parsed_url), Ok(parsed_repo)) = (reqwest::Url::parse(url), reqwest::Url::parse(repo))
+    else {
+        return false;
+    };
+
+    if parsed_url.scheme() != parsed_repo.scheme() || !util::is_same_host(url, repo) {
+        return false;
+    }
+
+    parsed_url
+        .path()
+        .strip_prefix(parsed_repo.path().trim_end_matches('/'))
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
 pub struct MavenAPI<'a>(pub &'a App);
 
 impl<'a> MavenAPI<'a> {
+    /// Username and password for the private repository `url` is in, from `sources.maven`
+    pub fn get_credentials(&self, url: &str) -> Option<(String, Option<String>)> {
+        let config = &self.0.config.sources.maven;
+        let is_in = |repo: &str| is_in_repo(url, repo);
+
+        config
+            .url
+            .as_deref()
+            .filter(|repo| is_in(repo))
+            .and(config.username.clone())
+            .map(|username| (username, config.password.clone()))
+            .or_else(|| {
+                config
+                    .repositories
+                    .iter()
+                    .find(|repo| is_in(&repo.url))
+                    .map(|repo| (repo.username.clone(), repo.password.clone()))
+            })
+    }
+
+    /// `path` of the local file at `url` for the lockfile: relative to server.toml when
+    /// it's inside its folder, so the lockfile works in other checkouts
+    fn local_cache_path(&self, url: &str, path: &Path) -> String {
+        if !url.contains("://") {
+            return url.to_owned();
+        }
+
+        match path.strip_prefix(&self.0.server.path) {
+            Ok(relative) => relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => path.to_string_lossy().into_owned(),
+        }
+    }
+
+    /// The folder of a repository on disk, for `file://` urls and paths relative to server.toml
+    pub fn get_local_path(&self, url: &str) -> Option<PathBuf> {
+        if let Some(path) = url.strip_prefix("file://") {
+            Some(PathBuf::from(path))
+        } else if url.contains("://") {
+            None
+        } else {
+            Some(self.0.server.path.join(url))
+        }
+    }
+
+    pub async fn fetch_text(&self, url: &str) -> Result<String> {
+        if let Some(path) = self.get_local_path(url) {
+            return tokio::fs::read_to_string(&path)
+                .await
+                .context(format!("Reading '{}'", path.display()));
+        }
+
+        Ok(self
+            .0
+            .with_source_auth(self.0.http_client.get(url), url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?)
+    }
+
+    pub fn get_version_url(url: &str, group_id: &str, artifact_id: &str, version: &str) -> String {
+        format!(
+            "{url}/{}/{artifact_id}/{version}",
+            group_id.replace(['.', ':'], "/")
+        )
+    }
+
+    /// The version used in the filenames of `version`. For `-SNAPSHOT` versions that's
+    /// the timestamped version of the newest snapshot, from the version's maven-metadata.xml
+    pub async fn fetch_file_version(
+        &self,
+        url: &str,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+        classifier: Option<&str>,
+        extension: &str,
+    ) -> Result<String> {
+        if !version.ends_with("-SNAPSHOT") {
+            return Ok(version.to_owned());
+        }
+
+        // snapshots installed locally don't have the metadata, their files keep `-SNAPSHOT`
+        let xml = match self
+            .fetch_text(&format!(
+                "{}/maven-metadata.xml",
+                Self::get_version_url(url, group_id, artifact_id, version)
+            ))
+            .await
+        {
+            Ok(xml) => xml,
+            Err(err) if is_not_found(&err) => return Ok(version.to_owned()),
+            Err(err) => {
+                return Err(err.context(format!(
+                    "Fetching the snapshots of {group_id}:{artifact_id}:{version}"
+                )))
+            }
+        };
+
+        let doc = roxmltree::Document::parse(&xml)?;
+
+        let snapshot_version = doc
+            .descendants()
+            .filter(|node| node.has_tag_name("snapshotVersion"))
+            .find_map(|node| {
+                let child = |name: &str| {
+                    node.children()
+                        .find(|c| c.has_tag_name(name))
+                        .and_then(|c| c.text())
+                };
+
+                (child("classifier") == classifier && child("extension") == Some(extension))
+                    .then(|| child("value"))
+                    .flatten()
+            });
+
+        Ok(match snapshot_version {
+            Some(value) => value.to_owned(),
+            None => match (doc.get_text("timestamp"), doc.get_text("buildNumber")) {
+                (Ok(timestamp), Ok(build)) => format!(
+                    "{}-{timestamp}-{build}",
+                    version.trim_end_matches("-SNAPSHOT")
+                ),
+                _ => version.to_owned(),
+            },
+        })
+    }
+
+    /// Hashes from the `.sha256` or `.sha1` file next to the file at `url`, if there is one
+    pub async fn fetch_sidecar_hashes(&self, url: &str) -> HashMap<String, String> {
+        for (hash_name, len) in [("sha256", 64), ("sha1", 40)] {
+            let Ok(text) = self.fetch_text(&format!("{url}.{hash_name}")).await else {
+                continue;
+            };
+
+            // some repositories add the filename after the hash
+            if let Some(hash) = text
+                .split_whitespace()
+                .next()
+                .filter(|h| h.len() == len && h.chars().all(|c| c.is_ascii_hexdigit()))
+            {
+                return HashMap::from([(hash_name.to_owned(), hash.to_lowercase())]);
+            }
+        }
+
+        HashMap::new()
+    }
+
     pub fn get_metadata_url(url: &str, group_id: &str, artifact_id: &str) -> String {
         format!(
             "{url}/{}/{artifact_id}/maven-metadata.xml",
@@ -110,7 +310,7 @@ impl<'a> MavenAPI<'a> {
     }
 
     pub async fn fetch_metadata_url(&self, url: &str) -> Result<MavenMetadata> {
-        let xml = self.0.http_client.get(url).send().await?.text().await?;
+        let xml = self.fetch_text(url).await?;
 
         let doc = roxmltree::Document::parse(&xml)?;
 
@@ -129,12 +329,7 @@ impl<'a> MavenAPI<'a> {
         artifact_id: &str,
     ) -> Result<(String, Vec<String>)> {
         let xml = self
-            .0
-            .http_client
-            .get(Self::get_metadata_url(url, group_id, artifact_id))
-            .send()
-            .await?
-            .text()
+            .fetch_text(&Self::get_metadata_url(url, group_id, artifact_id))
             .await?;
 
         let doc = roxmltree::Document::parse(&xml)?;
@@ -185,27 +380,82 @@ impl<'a> MavenAPI<'a> {
         version: &str,
         file: &str,
     ) -> Result<ResolvedFile> {
+        self.resolve_artifact(url, group_id, artifact_id, version, file, None, None)
+            .await
+    }
+
+    /// Resolve a file of an artifact. `${classifier}` in `file` is replaced with
+    /// `-<classifier>` if there is one, and `.<extension>` (`.jar` by default) is
+    /// added if `file` doesn't have an extension yet
+    #[allow(clippy::too_many_arguments)]
+    pub async fn resolve_artifact(
+        &self,
+        url: &str,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+        file: &str,
+        classifier: Option<&str>,
+        extension: Option<&str>,
+    ) -> Result<ResolvedFile> {
+        let url = url.trim_end_matches('/');
+        let extension = extension.unwrap_or("jar");
+
         let version = self
             .fetch_version(url, group_id, artifact_id, version)
             .await?;
 
+        let file_version = self
+            .fetch_file_version(url, group_id, artifact_id, &version, classifier, extension)
+            .await?;
+
         let file = file
             .replace("${artifact}", artifact_id)
-            .replace("${version}", &version)
+            .replace("${version}", &file_version)
+            .replace(
+                "${classifier}",
+                &classifier.map(|c| format!("-{c}")).unwrap_or_default(),
+            )
             .replace("${mcversion}", self.0.mc_version())
             .replace("${mcver}", self.0.mc_version());
 
-        let file = if file.contains('.') {
+        let file = if has_extension(&file) {
             file
         } else {
-            format!("{file}.jar")
+            format!("{file}.{extension}")
         };
 
         let download_url = format!(
-            "{url}/{}/{artifact_id}/{version}/{file}",
-            group_id.replace('.', "/"),
+            "{}/{file}",
+            Self::get_version_url(url, group_id, artifact_id, &version)
         );
 
+        // the timestamped version of snapshots
+        let file_version = (file_version != version).then_some(file_version);
+
+        if let Some(path) = self.get_local_path(&download_url) {
+            let meta = path
+                .metadata()
+                .context(format!("Couldn't find '{}'", path.display()))?;
+
+            // local snapshots get replaced in place, so they're always checked by hash
+            let hash = App::hash_file(&path, "sha256").await?;
+
+            return Ok(ResolvedFile {
+                cache: CacheStrategy::Local {
+                    path: self.local_cache_path(&download_url, &path),
+                },
+                url: download_url,
+                filename: file,
+                size: Some(meta.len()),
+                hashes: HashMap::from([("sha256".to_owned(), hash)]),
+                version: file_version,
+                mirrors: vec![],
+            });
+        }
+
+        let hashes = self.fetch_sidecar_hashes(&download_url).await;
+
         let cached_file_path = format!(
             "{}/{}/{artifact_id}/{version}/{file}",
             crate::util::url_to_folder(url),
@@ -220,7 +470,178 @@ impl<'a> MavenAPI<'a> {
                 path: cached_file_path,
             },
             size: None,
-            hashes: HashMap::new(),
+            hashes,
+            version: file_version,
+            mirrors: vec![],
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::Builder;
+
+    use crate::app::BaseApp;
+
+    use super::*;
+
+    #[test]
+    fn finds_extensions() {
+        assert!(has_extension("plugin-1.0.jar"));
+        assert!(has_extension("pack.zip"));
+        assert!(!has_extension("plugin-1.2"));
+        assert!(!has_extension("plugin-1.0-SNAPSHOT"));
+    }
+
+    #[test]
+    fn matches_repositories() {
+        let repo = "https://repo.example.com/maven/";
+
+        assert!(is_in_repo("https://repo.example.com/maven/a/b.jar", repo));
+        assert!(is_in_repo("https://repo.example.com/maven", repo));
+        assert!(!is_in_repo(
+            "https://repo.example.com/maven-evil/a.jar",
+            repo
+        ));
+        assert!(!is_in_repo(
+            "https://repo.example.com.evil.net/maven/a.jar",
+            repo
+        ));
+        assert!(!is_in_repo("http://repo.example.com/maven/a.jar", repo));
+        assert!(!is_in_repo(
+            "https://repo.example.com:8443/maven/a.jar",
+            repo
+        ));
+    }
+
+    #[tokio::test]
+    async fn resolves_snapshots_from_a_local_repository() {
+        let repo = Builder::new().prefix("mcman-maven-test").tempdir().unwrap();
+        let artifact_dir = repo.path().join("com/example/plugin");
+        let version_dir = artifact_dir.join("1.0-SNAPSHOT");
+        fs::create_dir_all(&version_dir).unwrap();
+
+        fs::write(
+            artifact_dir.join("maven-metadata.xml"),
+            "<metadata><groupId>com.example</groupId><artifactId>plugin</artifactId>
+            <versioning><latest>1.0-SNAPSHOT</latest><versions>
+            <version>0.9</version><version>1.0-SNAPSHOT</version>
+            </versions></versioning></metadata>",
+        )
+        .unwrap();
+        fs::write(
+            version_dir.join("maven-metadata.xml"),
+            "<metadata><versioning><snapshot><timestamp>20240101.120000</timestamp>
+            <buildNumber>3</buildNumber></snapshot><snapshotVersions>
+            <snapshotVersion><classifier>sources</classifier><extension>jar</extension>
+            <value>1.0-20240101.120000-2</value></snapshotVersion>
+            <snapshotVersion><extension>jar</extension>
+            <value>1.0-20240101.120000-3</value></snapshotVersion>
+            </snapshotVersions></versioning></metadata>",
+        )
+        .unwrap();
+        fs::write(version_dir.join("plugin-1.0-20240101.120000-3.jar"), b"jar").unwrap();
+
+        let app = BaseApp::new()
+            .unwrap()
+            .upgrade_with_default_server()
+            .unwrap();
+        let url = format!("file://{}", repo.path().display());
+
+        let resolved = app
+            .maven()
+            .resolve_source(
+                &url,
+                "com.example",
+                "plugin",
+                "latest",
+                "${artifact}-${version}",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.filename, "plugin-1.0-20240101.120000-3.jar");
+        assert_eq!(resolved.version.as_deref(), Some("1.0-20240101.120000-3"));
+        assert_eq!(resolved.size, Some(3));
+        assert_eq!(
+            resolved.hashes.get("sha256").map(String::as_str),
+            Some("0163f1eea7894350060624d315234d40c508ab251ba121714e234503045faadd")
+        );
+        assert!(matches!(resolved.cache, CacheStrategy::Local { .. }));
+
+        // a version without -SNAPSHOT keeps its name
+        fs::create_dir_all(artifact_dir.join("0.9")).unwrap();
+        fs::write(artifact_dir.join("0.9/plugin-0.9.jar"), b"jar").unwrap();
+        let resolved = app
+            .maven()
+            .resolve_source(
+                &url,
+                "com.example",
+                "plugin",
+                "0.9",
+                "${artifact}-${version}",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.filename, "plugin-0.9.jar");
+        assert_eq!(resolved.version, None);
+    }
+
+    #[tokio::test]
+    async fn locks_repositories_next_to_server_toml_by_relative_path() {
+        let server_dir = Builder::new().prefix("mcman-maven-test").tempdir().unwrap();
+        let artifact_dir = server_dir.path().join("repo/com/example/plugin");
+        // installed locally, so there's no maven-metadata.xml for the snapshot
+        fs::create_dir_all(artifact_dir.join("2.0-SNAPSHOT")).unwrap();
+        fs::write(
+            artifact_dir.join("maven-metadata.xml"),
+            "<metadata><versioning><versions><version>2.0-SNAPSHOT</version></versions>
+            </versioning></metadata>",
+        )
+        .unwrap();
+        fs::write(
+            artifact_dir.join("2.0-SNAPSHOT/plugin-2.0-SNAPSHOT.jar"),
+            b"jar",
+        )
+        .unwrap();
+
+        let mut app = BaseApp::new()
+            .unwrap()
+            .upgrade_with_default_server()
+            .unwrap();
+        app.server.path = server_dir.path().to_owned();
+
+        let resolved = app
+            .maven()
+            .resolve_source(
+                "repo",
+                "com.example",
+                "plugin",
+                "2.0-SNAPSHOT",
+                "${artifact}-${version}",
+            )
+            .await
+            .unwrap();
+
+        let path = "repo/com/example/plugin/2.0-SNAPSHOT/plugin-2.0-SNAPSHOT.jar";
+        assert_eq!(resolved.url, path);
+        assert_eq!(resolved.version, None);
+        assert!(matches!(resolved.cache, CacheStrategy::Local { path: p } if p == path));
+
+        let resolved = app
+            .maven()
+            .resolve_source(
+                &format!("file://{}/repo", server_dir.path().display()),
+                "com.example",
+                "plugin",
+                "2.0-SNAPSHOT",
+                "${artifact}-${version}",
+            )
+            .await
+            .unwrap();
+        assert!(matches!(resolved.cache, CacheStrategy::Local { path: p } if p == path));
+    }
+}
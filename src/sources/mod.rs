@@ -1,7 +1,10 @@
+pub mod curseforge;
 pub mod curserinth;
 pub mod fabric;
 pub mod forge;
+pub mod gitea;
 pub mod github;
+pub mod gitlab;
 pub mod hangar;
 pub mod jenkins;
 pub mod maven;
@@ -1,12 +1,17 @@
-use std::{borrow::Cow, collections::HashMap, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tokio::time::sleep;
 
 use crate::{
     app::{App, CacheStrategy, ResolvedFile},
-    model::{ServerType, SoftwareType},
+    model::{Channel, ServerType, SoftwareType, VersionFilter, VersionInfo, VersionSelector},
 };
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -82,6 +87,30 @@ pub enum VersionType {
     Alpha,
 }
 
+impl From<&VersionType> for Channel {
+    fn from(value: &VersionType) -> Self {
+        match value {
+            VersionType::Release => Self::Release,
+            VersionType::Beta => Self::Beta,
+            VersionType::Alpha => Self::Alpha,
+        }
+    }
+}
+
+impl VersionInfo for ModrinthVersion {
+    fn version_number(&self) -> &str {
+        &self.version_number
+    }
+
+    fn channel(&self) -> Channel {
+        (&self.version_type).into()
+    }
+
+    fn is_named(&self, version: &str) -> bool {
+        self.id == version || self.name == version || self.version_number == version
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum ModrinthStatus {
@@ -103,6 +132,29 @@ pub struct ModrinthFile {
     // file_type omitted
 }
 
+/// A required dependency that isn't installed, from [`ModrinthAPI::resolve_dependencies`]
+#[derive(Debug, Clone)]
+pub struct MissingDependency {
+    pub project: ModrinthProject,
+    pub version: ModrinthVersion,
+    /// Name of the addon or project that requires it
+    pub required_by: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModrinthDependencies {
+    /// Missing projects in the order they were found, dependencies of dependencies included
+    pub missing: Vec<MissingDependency>,
+    /// Pairs of an addon and a project it's incompatible with that are both installed
+    pub conflicts: Vec<(String, String)>,
+}
+
+#[derive(Debug, Serialize)]
+struct HashesRequest<'a> {
+    hashes: &'a [String],
+    algorithm: &'a str,
+}
+
 pub trait ModrinthWaitRatelimit<T> {
     async fn wait_ratelimit(self) -> Result<T>;
 }
@@ -160,38 +212,40 @@ impl<'a> ModrinthAPI<'a> {
     pub async fn fetch_versions(&self, id: &str) -> Result<Vec<ModrinthVersion>> {
         let versions = self.fetch_all_versions(id).await?;
 
-        Ok(self.filter_versions(&versions))
+        Ok(self.filter_versions(&versions, VersionFilter::default()))
     }
 
-    pub async fn fetch_version(&self, id: &str, version: &str) -> Result<ModrinthVersion> {
+    pub async fn fetch_version(
+        &self,
+        id: &str,
+        version: &str,
+        filter: VersionFilter<'_>,
+    ) -> Result<ModrinthVersion> {
         let all_versions = self.fetch_all_versions(id).await?;
-        let versions = self.filter_versions(&all_versions);
+        let versions = self.filter_versions(&all_versions, filter);
 
         let ver = version.replace("${mcver}", self.0.mc_version());
         let ver = ver.replace("${mcversion}", self.0.mc_version());
+        let selector = VersionSelector::parse(&ver);
 
-        let version_data = if let Some(v) = match ver.as_str() {
-            "latest" => versions.first(),
-            ver => versions
-                .iter()
-                .find(|v| v.id == ver || v.name == ver || v.version_number == ver),
-        } {
+        let version_data = if let Some(v) = selector.select(&versions, filter.channel) {
             v.clone()
-        } else {
-            let v = match ver.as_str() {
-                "latest" => all_versions.first(),
-                ver => all_versions
-                    .iter()
-                    .find(|v| v.id == ver || v.name == ver || v.version_number == ver),
-            }
-            .ok_or(anyhow!(
-                "Couln't find version '{ver}' ('{version}') for Modrinth project '{id}'"
-            ))?
-            .clone();
+        } else if matches!(selector, VersionSelector::Exact(_)) {
+            // an exact version was asked for, so it's used even if it doesn't seem compatible
+            let v = selector
+                .select(&all_versions, filter.channel)
+                .ok_or(anyhow!(
+                    "Couln't find version '{ver}' ('{version}') for Modrinth project '{id}'"
+                ))?
+                .clone();
             self.0.warn(format!(
                 "Filtering failed for modrinth.com/mod/{id}/version/{ver}"
             ));
             v
+        } else {
+            bail!(
+                "No version matching '{ver}' ('{version}') of Modrinth project '{id}' is compatible with the server's version and loader"
+            );
         };
 
         Ok(version_data)
@@ -201,8 +255,9 @@ impl<'a> ModrinthAPI<'a> {
         &self,
         id: &str,
         version: &str,
+        filter: VersionFilter<'_>,
     ) -> Result<(ModrinthFile, ModrinthVersion)> {
-        let version = self.fetch_version(id, version).await?;
+        let version = self.fetch_version(id, version, filter).await?;
 
         Ok((
             version
@@ -241,7 +296,12 @@ impl<'a> ModrinthAPI<'a> {
         serde_json::to_string(&arr).unwrap()
     }
 
-    pub fn filter_versions(&self, list: &[ModrinthVersion]) -> Vec<ModrinthVersion> {
+    /// Versions for the server's loader and game version, or `filter.game_versions` if it has any
+    pub fn filter_versions(
+        &self,
+        list: &[ModrinthVersion],
+        filter: VersionFilter<'_>,
+    ) -> Vec<ModrinthVersion> {
         let is_proxy = self.0.server.jar.get_software_type() == SoftwareType::Proxy;
         let is_vanilla = matches!(self.0.server.jar, ServerType::Vanilla {});
 
@@ -249,7 +309,16 @@ impl<'a> ModrinthAPI<'a> {
         let loader = self.get_modrinth_name();
 
         list.iter()
-            .filter(|v| is_proxy || v.game_versions.iter().any(|s| s.as_str() == mcver))
+            .filter(|v| {
+                is_proxy
+                    || v.game_versions.iter().any(|s| {
+                        if filter.game_versions.is_empty() {
+                            s.as_str() == mcver
+                        } else {
+                            filter.game_versions.contains(s)
+                        }
+                    })
+            })
             .filter(|v| {
                 if let Some(n) = loader {
                     v.loaders
@@ -291,8 +360,149 @@ impl<'a> ModrinthAPI<'a> {
         .await
     }
 
-    pub async fn resolve_source(&self, id: &str, version: &str) -> Result<ResolvedFile> {
-        let (file, version) = self.fetch_file(id, version).await?;
+    /// Versions of files by their hashes, files that aren't on Modrinth are left out
+    pub async fn versions_from_hashes(
+        &self,
+        hashes: &[String],
+        algo: &str,
+    ) -> Result<HashMap<String, ModrinthVersion>> {
+        Ok(self
+            .0
+            .http_client
+            .post(format!("{API_URL}/version_files"))
+            .json(&HashesRequest {
+                hashes,
+                algorithm: algo,
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .wait_ratelimit()
+            .await?
+            .json()
+            .await?)
+    }
+
+    /// The Modrinth version of each file in `files`, in the same order
+    pub async fn versions_of_files(
+        &self,
+        files: &[PathBuf],
+    ) -> Result<Vec<Option<ModrinthVersion>>> {
+        let mut hashes = Vec::with_capacity(files.len());
+        for file in files {
+            hashes.push(App::hash_file(file, "sha512").await?);
+        }
+
+        let mut versions = if hashes.is_empty() {
+            HashMap::new()
+        } else {
+            self.versions_from_hashes(&hashes, "sha512").await?
+        };
+
+        Ok(hashes.iter().map(|hash| versions.remove(hash)).collect())
+    }
+
+    /// Walk the required dependencies of `roots` (a name and a version each)
+    /// transitively. The projects of `roots` and the project ids or slugs in
+    /// `installed` are not reported as missing
+    pub async fn resolve_dependencies(
+        &self,
+        roots: &[(String, ModrinthVersion)],
+        installed: &HashSet<String>,
+    ) -> Result<ModrinthDependencies> {
+        let mut installed = installed.clone();
+        let mut installed_versions = HashSet::new();
+        for (_, version) in roots {
+            installed.insert(version.project_id.clone());
+            installed_versions.insert(version.id.clone());
+        }
+
+        let mut queue = roots.to_vec();
+        let mut incompatible = vec![];
+        let mut result = ModrinthDependencies::default();
+
+        let mut idx = 0;
+        while let Some((name, version)) = queue.get(idx).cloned() {
+            idx += 1;
+
+            for dep in &version.dependencies {
+                match dep.dependency_type {
+                    Some(DependencyType::Required) => {}
+                    Some(DependencyType::Incompatible) => {
+                        incompatible.push((name.clone(), dep.clone()));
+                        continue;
+                    }
+                    _ => continue,
+                }
+
+                if dep
+                    .project_id
+                    .as_ref()
+                    .is_some_and(|id| installed.contains(id))
+                {
+                    continue;
+                }
+
+                // dependencies with only a file_name aren't on modrinth
+                let dep_version = match (&dep.version_id, &dep.project_id) {
+                    (Some(version_id), _) => self.fetch_version_by_id(version_id).await?,
+                    (None, Some(project_id)) => {
+                        self.fetch_version(project_id, "latest", VersionFilter::default())
+                            .await?
+                    }
+                    (None, None) => continue,
+                };
+
+                if installed.contains(&dep_version.project_id) {
+                    continue;
+                }
+
+                let project = self.fetch_project(&dep_version.project_id).await?;
+                if installed.contains(&project.slug) {
+                    continue;
+                }
+
+                installed.insert(dep_version.project_id.clone());
+                installed_versions.insert(dep_version.id.clone());
+                queue.push((project.title.clone(), dep_version.clone()));
+                result.missing.push(MissingDependency {
+                    project,
+                    version: dep_version,
+                    required_by: name.clone(),
+                });
+            }
+        }
+
+        for (name, dep) in incompatible {
+            let conflicting = match (&dep.project_id, &dep.version_id) {
+                (Some(project_id), _) if installed.contains(project_id) => project_id.clone(),
+                (_, Some(version_id)) if installed_versions.contains(version_id) => {
+                    self.fetch_version_by_id(version_id).await?.project_id
+                }
+                _ => continue,
+            };
+
+            let title = self
+                .fetch_project(&conflicting)
+                .await
+                .map_or(conflicting, |p| p.title);
+            result.conflicts.push((name, title));
+        }
+
+        Ok(result)
+    }
+
+    pub async fn fetch_version_by_id(&self, id: &str) -> Result<ModrinthVersion> {
+        self.fetch_api(&format!("{API_URL}/version/{id}")).await
+    }
+
+    pub async fn resolve_source(
+        &self,
+        id: &str,
+        version: &str,
+        filter: VersionFilter<'_>,
+    ) -> Result<ResolvedFile> {
+        let (file, version) = self.fetch_file(id, version, filter).await?;
 
         let cached_file_path = format!("{id}/{}/{}", version.id, file.filename);
 
@@ -305,6 +515,8 @@ impl<'a> ModrinthAPI<'a> {
             },
             size: Some(file.size),
             hashes: file.hashes,
+            version: Some(version.version_number),
+            mirrors: vec![],
         })
     }
 }
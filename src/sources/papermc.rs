@@ -101,6 +101,8 @@ impl<'a> PaperMCAPI<'a> {
             },
             size: None,
             hashes: HashMap::new(),
+            version: None,
+            mirrors: vec![],
         })
     }
 }
@@ -65,6 +65,8 @@ impl<'a> PurpurAPI<'a> {
             },
             size: None,
             hashes: HashMap::from([("md5".to_owned(), resolved_build.md5)]),
+            version: None,
+            mirrors: vec![],
         })
     }
 }
@@ -90,6 +90,8 @@ impl<'a> SpigotAPI<'a> {
             },
             size: None,
             hashes: HashMap::new(),
+            version: None,
+            mirrors: vec![],
         })
     }
 }
@@ -52,6 +52,8 @@ impl<'a> VanillaAPI<'a> {
             },
             size: Some(file.size as u64),
             hashes: HashMap::from([("sha1".to_owned(), file.sha1.clone())]),
+            version: None,
+            mirrors: vec![],
         })
     }
 }
@@ -44,13 +44,14 @@ pub fn get_git_remote() -> Result<String> {
         .map_or(path.clone(), ToOwned::to_owned))
 }
 
-pub fn write_git() -> Result<()> {
-    write_gitignore()?;
+/// `output_dirs` are the names of the output directories to ignore
+pub fn write_git(output_dirs: &[String]) -> Result<()> {
+    write_gitignore(output_dirs)?;
     write_gitattributes()?;
     Ok(())
 }
 
-pub fn write_gitignore() -> Result<PathBuf> {
+pub fn write_gitignore(output_dirs: &[String]) -> Result<PathBuf> {
     let root = get_git_root().context("Couldn't get repo root")?;
 
     let gitignore_path = Path::new(&root).join(".gitignore");
@@ -63,12 +64,26 @@ pub fn write_gitignore() -> Result<PathBuf> {
 
     let mut list = contents.split('\n').collect::<Vec<_>>();
 
-    for (ignore, comment) in [
-        ("**/server", "# mcman: Exclude mcman build outputs"),
-        ("*.mrpack", "# mcman: Exclude exported mrpacks"),
-        ("**/.env", "# mcman: Exclude local dotenv files"),
-    ] {
-        if !list.contains(&ignore) {
+    let mut ignores = vec![];
+    for (idx, name) in output_dirs.iter().enumerate() {
+        let comment = if idx == 0 {
+            "# mcman: Exclude mcman build outputs"
+        } else {
+            ""
+        };
+
+        ignores.push((format!("**/{name}"), comment));
+        for suffix in ["staging", "previous", "rollback"] {
+            ignores.push((format!("**/{name}.{suffix}"), ""));
+        }
+    }
+    ignores.extend([
+        ("*.mrpack".to_owned(), "# mcman: Exclude exported mrpacks"),
+        ("**/.env".to_owned(), "# mcman: Exclude local dotenv files"),
+    ]);
+
+    for (ignore, comment) in &ignores {
+        if !list.contains(&ignore.as_str()) {
             if !comment.is_empty() {
                 list.push(comment);
             }
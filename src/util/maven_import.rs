@@ -1,6 +1,6 @@
 use anyhow::{anyhow, bail, Result};
 
-use crate::model::Downloadable;
+use crate::model::{DownloadOptions, Downloadable};
 
 macro_rules! dependency {
     ($xml:ident, $name:literal) => {
@@ -35,6 +35,9 @@ pub fn import_from_maven_dependency_xml(url: &str, xml: &str) -> Result<Download
         group,
         version,
         filename: "${artifact}-${version}".to_owned(),
+        classifier: None,
+        extension: None,
+        options: DownloadOptions::default(),
     })
 }
 
@@ -65,6 +68,9 @@ pub fn import_from_gradle_dependency(url: &str, imp: &str) -> Result<Downloadabl
         artifact: li[1].to_owned(),
         version: li[2].to_owned(),
         filename: "${artifact}-${version}".to_owned(),
+        classifier: None,
+        extension: None,
+        options: DownloadOptions::default(),
     })
 }
 
@@ -87,5 +93,8 @@ pub fn import_from_sbt(url: &str, sbt: &str) -> Result<Downloadable> {
         artifact: li[1].to_owned(),
         version: li[2].to_owned(),
         filename: "${artifact}-${version}".to_owned(),
+        classifier: None,
+        extension: None,
+        options: DownloadOptions::default(),
     })
 }
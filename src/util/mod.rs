@@ -1,11 +1,12 @@
-use std::{borrow::Cow, cmp::Ordering};
+use std::{borrow::Cow, cmp::Ordering, io::ErrorKind, path::Path};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use regex::Regex;
 
 pub mod env;
 pub mod maven_import;
 pub mod md;
+pub mod toml_doc;
 
 pub struct SelectItem<T>(pub T, pub Cow<'static, str>);
 
@@ -18,6 +19,27 @@ impl<T> ToString for SelectItem<T> {
     }
 }
 
+/// Remove the file at `path` if there's one, so writing to it creates a new file
+/// instead of changing one that might be hardlinked somewhere else
+pub fn unlink(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        o => o,
+    }
+}
+
+/// Merge `over` into `base`, tables are merged key by key and everything else is replaced
+pub fn merge_toml(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(over)) => merge_toml(base, over),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 pub fn is_default<T: Default + PartialEq>(t: &T) -> bool {
     t == &T::default()
 }
@@ -61,6 +83,63 @@ pub fn get_latest_semver(list: &[String]) -> Option<String> {
     list.last().map(|v| v.join("."))
 }
 
+/// Pick the item named `pattern`: `first` for the first item, a regex between slashes
+/// like `/^Foo-\d+\.jar$/`, otherwise the exact name, a glob like `*.jar` or the first
+/// name containing it. Errors if the regex or glob is invalid
+pub fn select_by_name<'a, T>(
+    items: &'a [T],
+    name: impl Fn(&T) -> &str,
+    pattern: &str,
+) -> Result<Option<&'a T>> {
+    Ok(match pattern {
+        "" | "first" | "any" => items.first(),
+        pattern if pattern.len() > 2 && pattern.starts_with('/') && pattern.ends_with('/') => {
+            let re = Regex::new(&pattern[1..pattern.len() - 1])
+                .context(format!("Invalid regex in '{pattern}'"))?;
+            items.iter().find(|i| re.is_match(name(i)))
+        }
+        pattern => {
+            let glob = pattern
+                .contains(['*', '?', '['])
+                .then(|| glob::Pattern::new(pattern))
+                .transpose()
+                .context(format!("Invalid glob '{pattern}'"))?;
+
+            items
+                .iter()
+                .find(|i| name(i) == pattern)
+                .or_else(|| {
+                    items
+                        .iter()
+                        .find(|i| glob.as_ref().is_some_and(|g| g.matches(name(i))))
+                })
+                .or_else(|| items.iter().find(|i| name(i).contains(pattern)))
+        }
+    })
+}
+
+/// Percent-encode `s` so it can be used as one segment of a url path
+pub fn encode_path_segment(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Whether both urls point to the same host and port
+pub fn is_same_host(a: &str, b: &str) -> bool {
+    match (reqwest::Url::parse(a), reqwest::Url::parse(b)) {
+        (Ok(a), Ok(b)) => {
+            a.host_str() == b.host_str() && a.port_or_known_default() == b.port_or_known_default()
+        }
+        _ => false,
+    }
+}
+
 /// ci.luckto.me => ci-lucko-me
 pub fn url_to_folder(url: &str) -> String {
     url.replace("https://", "")
@@ -82,3 +161,51 @@ pub fn sanitize(s: &str) -> Result<String> {
         )
         .to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 3] = ["Foo-sources.jar", "Foo-1.2.jar", "Bar-1.0.jar"];
+
+    fn select(pattern: &str) -> Result<Option<&'static str>> {
+        Ok(select_by_name(&NAMES, |n| n, pattern)?.copied())
+    }
+
+    #[test]
+    fn selects_by_name() {
+        assert_eq!(select("first").unwrap(), Some("Foo-sources.jar"));
+        assert_eq!(select("Bar-1.0.jar").unwrap(), Some("Bar-1.0.jar"));
+        assert_eq!(select("Foo-*.*.jar").unwrap(), Some("Foo-1.2.jar"));
+        assert_eq!(select(r"/^Foo-\d+/").unwrap(), Some("Foo-1.2.jar"));
+        assert_eq!(select("Bar").unwrap(), Some("Bar-1.0.jar"));
+        assert_eq!(select("Baz").unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        assert!(select("/Foo-(/").is_err());
+    }
+
+    #[test]
+    fn invalid_glob_is_an_error() {
+        assert!(select("Foo-[.jar").is_err());
+        assert!(select("Foo-***.jar").is_err());
+    }
+
+    #[test]
+    fn encodes_path_segments() {
+        assert_eq!(encode_path_segment("v1.2.0"), "v1.2.0");
+        assert_eq!(
+            encode_path_segment("release/1.0 & co"),
+            "release%2F1.0%20%26%20co"
+        );
+    }
+
+    #[test]
+    fn compares_hosts() {
+        assert!(is_same_host("https://a.com/x", "https://a.com:443/y"));
+        assert!(!is_same_host("https://a.com/x", "https://a.com:8443/x"));
+        assert!(!is_same_host("https://a.com/x", "https://a.com.evil.net/x"));
+    }
+}
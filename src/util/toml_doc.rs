@@ -0,0 +1,205 @@
+use std::{fs, io::ErrorKind, path::Path};
+
+use anyhow::{Context, Result};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, TableLike};
+
+/// Write `table` to the toml file at `path`. If the file already exists, only the values
+/// that changed since `old` (what the file was loaded as) are rewritten so comments,
+/// ordering, formatting and values written by hand are kept
+pub fn write(path: &Path, old: Option<&toml::Table>, table: &toml::Table) -> Result<()> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => {
+            let mut doc = data.parse::<DocumentMut>().context(format!(
+                "Parsing {}, fix it or remove it to write it again",
+                path.display()
+            ))?;
+            update_table(doc.as_table_mut(), old, table);
+            doc.to_string()
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => toml::to_string_pretty(table)?,
+        Err(e) => return Err(e).context(format!("Reading {}", path.display())),
+    };
+
+    fs::write(path, data).context(format!("Writing {}", path.display()))
+}
+
+/// Make `table` match `new`: keys that were in `old` but aren't in `new` anymore are
+/// dropped, new keys are added at the end and changed values are replaced in place
+fn update_table(table: &mut dyn TableLike, old: Option<&toml::Table>, new: &toml::Table) {
+    if let Some(old) = old {
+        for key in old.keys().filter(|key| !new.contains_key(*key)) {
+            table.remove(key);
+        }
+    }
+
+    for (key, value) in new {
+        let old = old.and_then(|old| old.get(key));
+        match table.get_mut(key) {
+            Some(item) => update_item(item, old, value),
+            // a default that was filled in when loading
+            None if old == Some(value) => {}
+            None => {
+                table.insert(key, to_item(value));
+            }
+        }
+    }
+}
+
+fn update_item(item: &mut Item, old: Option<&toml::Value>, new: &toml::Value) {
+    if old == Some(new) || from_item(item).as_ref() == Some(new) {
+        return;
+    }
+
+    if let (Some(table), toml::Value::Table(new)) = (item.as_table_like_mut(), new) {
+        update_table(table, old.and_then(toml::Value::as_table), new);
+        return;
+    }
+
+    match (item, new) {
+        (Item::ArrayOfTables(list), toml::Value::Array(new)) if is_array_of_tables(new) => {
+            update_tables(list, old.and_then(toml::Value::as_array), new);
+        }
+        (Item::Value(old), new) => {
+            let decor = old.decor().clone();
+            *old = to_value(new);
+            *old.decor_mut() = decor;
+        }
+        (item, new) => *item = to_item(new),
+    }
+}
+
+/// Entries that didn't change are kept as they are, changed ones are updated in place
+/// of an entry that was removed and the rest are added at the end
+fn update_tables(list: &mut ArrayOfTables, old: Option<&Vec<toml::Value>>, new: &[toml::Value]) {
+    // the loaded value only lines up with the document if nothing was added or removed
+    let old = old.filter(|old| old.len() == list.len());
+    let mut previous = list
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(idx, table)| Some((table, old.map(|old| &old[idx]))))
+        .collect::<Vec<_>>();
+    let mut entries = vec![None; new.len()];
+
+    for (entry, value) in entries.iter_mut().zip(new) {
+        if let Some(idx) = previous.iter().position(|prev| {
+            prev.as_ref()
+                .is_some_and(|(table, old)| *old == Some(value) || table_value(table) == *value)
+        }) {
+            *entry = previous[idx].take().map(|(table, _)| table);
+        }
+    }
+
+    let mut leftover = previous.into_iter().flatten();
+    for (entry, value) in entries.iter_mut().zip(new) {
+        let (None, toml::Value::Table(value)) = (&entry, value) else {
+            continue;
+        };
+
+        *entry = Some(match leftover.next() {
+            Some((mut table, old)) => {
+                update_table(&mut table, old.and_then(toml::Value::as_table), value);
+                table
+            }
+            None => to_table(value),
+        });
+    }
+
+    list.clear();
+    for table in entries.into_iter().flatten() {
+        list.push(table);
+    }
+}
+
+fn is_array_of_tables(list: &[toml::Value]) -> bool {
+    !list.is_empty() && list.iter().all(toml::Value::is_table)
+}
+
+fn to_item(value: &toml::Value) -> Item {
+    match value {
+        toml::Value::Table(table) => Item::Table(to_table(table)),
+        toml::Value::Array(list) if is_array_of_tables(list) => {
+            let mut tables = ArrayOfTables::new();
+            for value in list {
+                if let toml::Value::Table(table) = value {
+                    tables.push(to_table(table));
+                }
+            }
+            Item::ArrayOfTables(tables)
+        }
+        value => Item::Value(to_value(value)),
+    }
+}
+
+fn to_table(table: &toml::Table) -> toml_edit::Table {
+    let mut new = toml_edit::Table::new();
+    new.set_implicit(true);
+    for (key, value) in table {
+        new.insert(key, to_item(value));
+    }
+    new
+}
+
+fn to_value(value: &toml::Value) -> toml_edit::Value {
+    match value {
+        toml::Value::String(s) => s.as_str().into(),
+        toml::Value::Integer(i) => (*i).into(),
+        toml::Value::Float(f) => (*f).into(),
+        toml::Value::Boolean(b) => (*b).into(),
+        toml::Value::Datetime(dt) => dt
+            .to_string()
+            .parse::<toml_edit::Datetime>()
+            .map_or_else(|_| dt.to_string().into(), Into::into),
+        toml::Value::Array(list) => list
+            .iter()
+            .map(to_value)
+            .collect::<toml_edit::Array>()
+            .into(),
+        toml::Value::Table(table) => table
+            .iter()
+            .map(|(k, v)| (k.clone(), to_value(v)))
+            .collect::<toml_edit::InlineTable>()
+            .into(),
+    }
+}
+
+fn from_item(item: &Item) -> Option<toml::Value> {
+    match item {
+        Item::None => None,
+        Item::Value(value) => from_value(value),
+        Item::Table(table) => Some(table_value(table)),
+        Item::ArrayOfTables(list) => {
+            Some(toml::Value::Array(list.iter().map(table_value).collect()))
+        }
+    }
+}
+
+fn table_value(table: &toml_edit::Table) -> toml::Value {
+    toml::Value::Table(
+        table
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_owned(), from_item(v)?)))
+            .collect(),
+    )
+}
+
+fn from_value(value: &toml_edit::Value) -> Option<toml::Value> {
+    Some(match value {
+        toml_edit::Value::String(s) => toml::Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => toml::Value::Integer(*i.value()),
+        toml_edit::Value::Float(f) => toml::Value::Float(*f.value()),
+        toml_edit::Value::Boolean(b) => toml::Value::Boolean(*b.value()),
+        toml_edit::Value::Datetime(dt) => {
+            toml::Value::Datetime(dt.value().to_string().parse().ok()?)
+        }
+        toml_edit::Value::Array(list) => {
+            toml::Value::Array(list.iter().map(from_value).collect::<Option<_>>()?)
+        }
+        toml_edit::Value::InlineTable(table) => toml::Value::Table(
+            table
+                .iter()
+                .map(|(k, v)| Some((k.to_owned(), from_value(v)?)))
+                .collect::<Option<_>>()?,
+        ),
+    })
+}